@@ -0,0 +1,132 @@
+//! Stores that keep version lookups across calls and processes.
+//!
+//! Without a store every call queries each shell binary again. Set one with
+//! [`Detector::cache`](crate::Detector::cache) to reuse versions for a while,
+//! e.g. in a prompt that runs on every command or in a dev-tool server. The
+//! built-in [`MemoryStore`] lives as long as the process, [`FileStore`] keeps
+//! entries on disk. Embedders plug in their own stores, such as sled or
+//! redis, by implementing [`CacheStore`].
+//!
+//! ```
+//! use shellver::Detector;
+//! use shellver::cache::MemoryStore;
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! let store = Arc::new(MemoryStore::default());
+//! let detector = Detector::new().cache(store, Duration::from_secs(3600));
+//! ```
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+/// A key-value store with expiring entries.
+///
+/// Keys and values are plain strings, such as `version:/usr/bin/bash` and
+/// `5.2.15`. Detection treats errors as misses, so a broken store only costs
+/// time.
+pub trait CacheStore: fmt::Debug + Send + Sync {
+    /// Returns the value stored under `key`, or `None` if it is missing or
+    /// expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be read.
+    fn get(&self, key: &str) -> io::Result<Option<String>>;
+
+    /// Stores `value` under `key` for `ttl`, replacing any previous value.
+    /// A `ttl` too large to add to the clock, such as [`Duration::MAX`],
+    /// never expires.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be written.
+    fn put(&self, key: &str, value: &str, ttl: Duration) -> io::Result<()>;
+}
+
+/// An in-process store, shared between detectors and threads.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    // `None` for entries that never expire.
+    entries: Mutex<HashMap<String, (String, Option<Instant>)>>,
+}
+
+impl CacheStore for MemoryStore {
+    fn get(&self, key: &str) -> io::Result<Option<String>> {
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let value = entries
+            .get(key)
+            .filter(|(_, expires)| expires.is_none_or(|expires| Instant::now() < expires))
+            .map(|(value, _)| value.clone());
+        drop(entries);
+        Ok(value)
+    }
+
+    fn put(&self, key: &str, value: &str, ttl: Duration) -> io::Result<()> {
+        let entry = (value.to_string(), Instant::now().checked_add(ttl));
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key.to_string(), entry);
+        Ok(())
+    }
+}
+
+/// A store keeping one file per key in a directory, shared between
+/// processes, e.g. under `$XDG_CACHE_HOME/shellver`.
+///
+/// Each file holds the expiry time in seconds since the Unix epoch on its
+/// first line, `u64::MAX` for entries that never expire, and the value after
+/// it.
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    /// Creates a store in `dir`, which is created on the first write.
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    // Keys contain slashes and colons, so file names are their hex bytes.
+    fn path(&self, key: &str) -> PathBuf {
+        let mut name = String::with_capacity(key.len() * 2);
+        for byte in key.bytes() {
+            _ = write!(name, "{byte:02x}");
+        }
+        self.dir.join(name)
+    }
+}
+
+impl CacheStore for FileStore {
+    fn get(&self, key: &str) -> io::Result<Option<String>> {
+        let text = match fs::read_to_string(self.path(key)) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "bad cache entry");
+        let (expires, value) = text.split_once('\n').ok_or_else(invalid)?;
+        let expires: u64 = expires.parse().map_err(|_| invalid())?;
+        Ok((unix_secs(SystemTime::now()) < expires).then(|| value.to_string()))
+    }
+
+    fn put(&self, key: &str, value: &str, ttl: Duration) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let expires = SystemTime::now()
+            .checked_add(ttl)
+            .map_or(u64::MAX, unix_secs);
+        fs::write(self.path(key), format!("{expires}\n{value}"))
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
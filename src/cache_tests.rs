@@ -0,0 +1,69 @@
+mod tests {
+    use crate::cache::*;
+    use std::env;
+    use std::fs;
+    use std::io;
+    use std::time::Duration;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    #[test]
+    fn memory_store_expires() {
+        let store = MemoryStore::default();
+        assert_eq!(store.get("version:/bin/bash").unwrap(), None);
+        store.put("version:/bin/bash", "5.2.15", HOUR).unwrap();
+        assert_eq!(
+            store.get("version:/bin/bash").unwrap().as_deref(),
+            Some("5.2.15")
+        );
+        store
+            .put("version:/bin/bash", "5.2.15", Duration::ZERO)
+            .unwrap();
+        assert_eq!(store.get("version:/bin/bash").unwrap(), None);
+    }
+
+    #[test]
+    fn memory_store_huge_ttl_never_expires() {
+        let store = MemoryStore::default();
+        store
+            .put("version:/bin/bash", "5.2.15", Duration::MAX)
+            .unwrap();
+        assert_eq!(
+            store.get("version:/bin/bash").unwrap().as_deref(),
+            Some("5.2.15")
+        );
+    }
+
+    #[test]
+    fn file_store_round_trip() {
+        let dir = env::temp_dir().join(format!("shellver-cache-{}", std::process::id()));
+        let store = FileStore::new(&dir);
+        assert_eq!(store.get("version:/usr/bin/zsh").unwrap(), None);
+        store.put("version:/usr/bin/zsh", "5.9", HOUR).unwrap();
+        store
+            .put("version:/usr/bin/fish", "3.7.1", Duration::ZERO)
+            .unwrap();
+        let zsh = store.get("version:/usr/bin/zsh");
+        let fish = store.get("version:/usr/bin/fish");
+        let entries = fs::read_dir(&dir).unwrap().count();
+        fs::write(dir.join("6b6579"), "soon\n1.0").unwrap();
+        let bad = store.get("key").unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(zsh.unwrap().as_deref(), Some("5.9"));
+        assert_eq!(fish.unwrap(), None);
+        assert_eq!(entries, 2);
+        assert_eq!(bad.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn file_store_huge_ttl_never_expires() {
+        let dir = env::temp_dir().join(format!("shellver-cache-max-{}", std::process::id()));
+        let store = FileStore::new(&dir);
+        store
+            .put("version:/usr/bin/zsh", "5.9", Duration::MAX)
+            .unwrap();
+        let zsh = store.get("version:/usr/bin/zsh");
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(zsh.unwrap().as_deref(), Some("5.9"));
+    }
+}
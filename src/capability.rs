@@ -0,0 +1,84 @@
+//! Shell capabilities, from a static table or probed empirically.
+
+/// A language feature a shell may support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Capability {
+    /// Indexed arrays, e.g. `a=(x y)`.
+    Arrays,
+    /// Associative arrays, e.g. `typeset -A map`.
+    AssociativeArrays,
+    /// The `[[ ... ]]` conditional expression.
+    DoubleBracket,
+    /// Process substitution, e.g. `<(cmd)`.
+    ProcessSubstitution,
+    /// Brace expansion, e.g. `file.{c,h}`.
+    BraceExpansion,
+}
+
+impl Capability {
+    /// Every capability, in a stable order.
+    pub const ALL: [Self; 5] = [
+        Self::Arrays,
+        Self::AssociativeArrays,
+        Self::DoubleBracket,
+        Self::ProcessSubstitution,
+        Self::BraceExpansion,
+    ];
+
+    /// Returns the `snake_case` name used in output.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Arrays => "arrays",
+            Self::AssociativeArrays => "associative_arrays",
+            Self::DoubleBracket => "double_bracket",
+            Self::ProcessSubstitution => "process_substitution",
+            Self::BraceExpansion => "brace_expansion",
+        }
+    }
+
+    /// Returns a `-c` snippet printing `ok` only if a POSIX-like shell supports
+    /// this capability. Unsupported syntax makes the shell fail before `echo`.
+    pub(crate) const fn snippet(self) -> &'static str {
+        match self {
+            Self::Arrays => "a=(x y); echo ok",
+            Self::AssociativeArrays => "typeset -A a && echo ok",
+            Self::DoubleBracket => "[[ x == x ]] && echo ok",
+            Self::ProcessSubstitution => "cat <(echo ok)",
+            Self::BraceExpansion => "set -- x{a,b}; [ \"$1\" = xa ] && echo ok",
+        }
+    }
+}
+
+/// Returns the capabilities of `name` according to the static table.
+#[must_use]
+pub fn of(name: &str) -> Vec<Capability> {
+    use Capability::{
+        Arrays, AssociativeArrays, BraceExpansion, DoubleBracket, ProcessSubstitution,
+    };
+    let caps: &[Capability] = match name {
+        "bash" | "zsh" | "ksh" | "osh" => &[
+            Arrays,
+            AssociativeArrays,
+            DoubleBracket,
+            ProcessSubstitution,
+            BraceExpansion,
+        ],
+        "mksh" => &[Arrays, DoubleBracket, BraceExpansion],
+        "yash" | "rc" | "es" => &[Arrays],
+        "tcsh" | "csh" | "fish" => &[Arrays, BraceExpansion],
+        "elvish" | "nu" | "xonsh" | "pwsh" | "ysh" | "ion" | "murex" => {
+            &[Arrays, AssociativeArrays]
+        }
+        _ => &[],
+    };
+    caps.to_vec()
+}
+
+/// Returns whether capabilities of `name` can be probed with [`Capability::snippet`].
+pub(crate) fn probeable(name: &str) -> bool {
+    matches!(
+        name,
+        "bash" | "zsh" | "sh" | "ksh" | "mksh" | "dash" | "ash" | "yash" | "osh"
+    )
+}
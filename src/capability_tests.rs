@@ -0,0 +1,25 @@
+mod tests {
+    use crate::capability::*;
+
+    #[test]
+    fn static_table() {
+        assert_eq!(of("bash"), Capability::ALL);
+        assert!(!of("mksh").contains(&Capability::AssociativeArrays));
+        assert!(of("dash").is_empty());
+    }
+
+    #[test]
+    fn snippets_print_ok() {
+        assert!(
+            Capability::ALL
+                .iter()
+                .all(|cap| cap.snippet().contains("ok"))
+        );
+    }
+
+    #[test]
+    fn probeable_shells() {
+        assert!(probeable("bash"));
+        assert!(!probeable("fish"));
+    }
+}
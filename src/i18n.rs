@@ -0,0 +1,381 @@
+//! Message tables for the human-facing CLI strings.
+//!
+//! Only text meant for people is translated. Machine output such as JSON keys,
+//! shell names and versions stays untouched.
+use shellver::Warning;
+use std::env;
+use std::sync::OnceLock;
+
+/// A supported interface language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    De,
+    Hu,
+}
+
+/// A translatable message. `{}` in the text is replaced by an argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    Error,
+    Warning,
+    UnknownCommand,
+    UnknownOption,
+    UnexpectedArgument,
+    MissingValue,
+    UnknownFormat,
+    InvalidTimeout,
+    InvalidPid,
+    UnknownColor,
+    TemplateRequired,
+    Unparsed,
+    NoVersion,
+    NoOutput,
+    VersionFromPath,
+    EnvironmentShell,
+    CommExeMismatch,
+    CompareNeedsTwo,
+    WhichNeedsOne,
+    CompletionsNeedsShell,
+    RequireNeedsOne,
+    Unsatisfied,
+    NotInstalled,
+    Yes,
+    No,
+    DoctorDetected,
+    DoctorProcUnreadable,
+    DoctorHidepid,
+    DoctorHopLimit,
+    DoctorOrphaned,
+    DoctorNamespace,
+    DoctorNoVersion,
+    DoctorShellUnset,
+    DoctorShellDiffers,
+    Usage,
+}
+
+/// Picks the language from `LC_ALL`, `LC_MESSAGES` and `LANG`, in that order.
+pub fn lang_from(var: impl Fn(&str) -> Option<String>) -> Lang {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(var)
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    match locale.get(..2) {
+        Some("de") => Lang::De,
+        Some("hu") => Lang::Hu,
+        _ => Lang::En,
+    }
+}
+
+fn lang() -> Lang {
+    static LANG: OnceLock<Lang> = OnceLock::new();
+    *LANG.get_or_init(|| lang_from(|key| env::var(key).ok()))
+}
+
+/// Returns `msg` in the language of the current locale.
+pub fn tr(msg: Msg) -> &'static str {
+    text(lang(), msg)
+}
+
+/// Returns `msg` in the current language with `{}` replaced by `arg`.
+pub fn tr_arg(msg: Msg, arg: &str) -> String {
+    tr(msg).replace("{}", arg)
+}
+
+/// Returns `msg` in English with `{}` replaced by `arg`, for machine output.
+pub fn en_arg(msg: Msg, arg: &str) -> String {
+    en(msg).replace("{}", arg)
+}
+
+/// Returns the translated text of a library warning, falling back to its
+/// English description for warnings without a table entry.
+pub fn tr_warning(warning: &Warning) -> String {
+    match warning {
+        Warning::VersionFromPath => tr(Msg::VersionFromPath).to_string(),
+        Warning::EnvironmentShell => tr(Msg::EnvironmentShell).to_string(),
+        Warning::CommExeMismatch => tr(Msg::CommExeMismatch).to_string(),
+        other => other.to_string(),
+    }
+}
+
+pub const fn text(lang: Lang, msg: Msg) -> &'static str {
+    match lang {
+        Lang::En => en(msg),
+        Lang::De => de(msg),
+        Lang::Hu => hu(msg),
+    }
+}
+
+const fn en(msg: Msg) -> &'static str {
+    match msg {
+        Msg::Error => "error",
+        Msg::Warning => "warning",
+        Msg::UnknownCommand => "unknown command: {}",
+        Msg::UnknownOption => "unknown option: {}",
+        Msg::UnexpectedArgument => "unexpected argument: {}",
+        Msg::MissingValue => "{} needs a value",
+        Msg::UnknownFormat => "unknown output format: {}",
+        Msg::InvalidTimeout => "invalid timeout: {}",
+        Msg::InvalidPid => "invalid pid: {}",
+        Msg::UnknownColor => "unknown --color value: {}",
+        Msg::TemplateRequired => "--output template needs --template",
+        Msg::Unparsed => "{}, unparsed version output:",
+        Msg::NoVersion => "the version of {} is unknown",
+        Msg::NoOutput => "(no output)",
+        Msg::VersionFromPath => "version from PATH binary, not running process",
+        Msg::EnvironmentShell => "shell from $SHELL, may not be the running shell",
+        Msg::CommExeMismatch => "comm/exe mismatch",
+        Msg::CompareNeedsTwo => "compare needs two shells",
+        Msg::WhichNeedsOne => "which needs a shell, optionally followed by a version requirement",
+        Msg::RequireNeedsOne => "require needs a version requirement, optionally after a shell",
+        Msg::Unsatisfied => "{} does not satisfy the requirement",
+        Msg::CompletionsNeedsShell => "completions needs one of bash, zsh or fish",
+        Msg::NotInstalled => "shell not installed: {}",
+        Msg::Yes => "yes",
+        Msg::No => "no",
+        Msg::DoctorDetected => "detected {}",
+        Msg::DoctorProcUnreadable => "cannot read the process chain: {}",
+        Msg::DoctorHidepid => "/proc is mounted with {}, processes of other users are hidden",
+        Msg::DoctorHopLimit => "no shell within {} parent processes, the chain is too long",
+        Msg::DoctorOrphaned => "started by {} rather than a shell, try --env-fallback",
+        Msg::DoctorNamespace => "in a nested PID namespace, pass the host's /proc with --proc-root",
+        Msg::DoctorNoVersion => "the version of {} is unknown, see shellver list",
+        Msg::DoctorShellUnset => "$SHELL is not set",
+        Msg::DoctorShellDiffers => "$SHELL is {}, which is not the running shell",
+        Msg::Usage => concat!(
+            "Usage: shellver [command] [options]\n",
+            "\n",
+            "Commands:\n",
+            "  detect               print the current shell and its version, the default\n",
+            "  ancestors            the parent process chain and which hops are shells\n",
+            "  completions <shell>  a completion script for bash, zsh or fish\n",
+            "  doctor               diagnose why detection fails\n",
+            "  dump                 a redacted report of the chain for bug reports\n",
+            "  env                  the result as variable assignments to eval\n",
+            "  info                 the long summary plus active environments\n",
+            "  list                 supported shells, their version command and installation\n",
+            "  survey               every shell process on the system\n",
+            "  which <shell> [req]  where a shell is installed\n",
+            "  compare <a> <b>      versions and capabilities side by side\n",
+            "  require [shell] <req> succeed if the shell version meets a requirement\n",
+            "  selfcheck            check that every installed shell's version parses\n",
+            "\n",
+            "Options:\n",
+            "  -a, --all            every shell in the parent chain\n",
+            "  -o, --output <f>     plain, long, json, yaml, table, porcelain or template\n",
+            "      --format <t>     format with placeholders such as {name} {version}\n",
+            "      --template <t>   same as --format\n",
+            "      --shell <name>   report the named shell instead of detecting one\n",
+            "      --color <when>   auto, always or never, honoring NO_COLOR\n",
+            "  -l, --long           same as --output long\n",
+            "      --json           same as --output json\n",
+            "      --porcelain      stable tab-separated lines for scripts\n",
+            "      --semver         only the version as major.minor.patch\n",
+            "      --major          only the major version\n",
+            "      --fingerprint    only the session fingerprint\n",
+            "      --watch[=<s>]    print again whenever the shell changes\n",
+            "      --no-version     skip the version for speed, only /proc is read\n",
+            "      --timeout <s>    bound each subprocess, in seconds\n",
+            "      --all-versions   every installation for which\n",
+            "      --proc-root <p>  walk another /proc\n",
+            "      --snapshot <d>   walk a /proc copy offline, without running anything\n",
+            "      --ps <file>      walk piped ps -ef output, - for stdin\n",
+            "      --pid <pid>      start the --ps walk at this process\n",
+            "      --env-fallback   fall back to $SHELL on any error\n",
+            "      --fallback <n>   report this shell when detection fails\n",
+            "      --strict         never fall back to $SHELL, fail without a version\n",
+            "  -q, --quiet          no warnings or log\n",
+            "  -v, --verbose        log version commands, -vv also hops and reads\n",
+            "  -h, --help           print this help\n",
+        ),
+    }
+}
+
+const fn de(msg: Msg) -> &'static str {
+    match msg {
+        Msg::Error => "Fehler",
+        Msg::Warning => "Warnung",
+        Msg::UnknownCommand => "unbekannter Befehl: {}",
+        Msg::UnknownOption => "unbekannte Option: {}",
+        Msg::UnexpectedArgument => "unerwartetes Argument: {}",
+        Msg::MissingValue => "{} benötigt einen Wert",
+        Msg::UnknownFormat => "unbekanntes Ausgabeformat: {}",
+        Msg::InvalidTimeout => "ungültiges Zeitlimit: {}",
+        Msg::InvalidPid => "ungültige PID: {}",
+        Msg::UnknownColor => "unbekannter Wert für --color: {}",
+        Msg::TemplateRequired => "--output template benötigt --template",
+        Msg::Unparsed => "{}, nicht erkannte Versionsausgabe:",
+        Msg::NoVersion => "die Version von {} ist unbekannt",
+        Msg::NoOutput => "(keine Ausgabe)",
+        Msg::VersionFromPath => "Version vom Programm im PATH, nicht vom laufenden Prozess",
+        Msg::EnvironmentShell => "Shell aus $SHELL, läuft möglicherweise nicht",
+        Msg::CommExeMismatch => "Prozessname und Programm stimmen nicht überein",
+        Msg::CompareNeedsTwo => "compare benötigt zwei Shells",
+        Msg::WhichNeedsOne => {
+            "which benötigt eine Shell, optional gefolgt von einer Versionsanforderung"
+        }
+        Msg::RequireNeedsOne => {
+            "require benötigt eine Versionsanforderung, optional nach einer Shell"
+        }
+        Msg::Unsatisfied => "{} erfüllt die Anforderung nicht",
+        Msg::CompletionsNeedsShell => "completions benötigt bash, zsh oder fish",
+        Msg::NotInstalled => "Shell nicht installiert: {}",
+        Msg::Yes => "ja",
+        Msg::No => "nein",
+        Msg::DoctorDetected => "erkannt: {}",
+        Msg::DoctorProcUnreadable => "Prozesskette nicht lesbar: {}",
+        Msg::DoctorHidepid => {
+            "/proc ist mit {} eingehängt, Prozesse anderer Benutzer sind verborgen"
+        }
+        Msg::DoctorHopLimit => "keine Shell in {} Elternprozessen, die Kette ist zu lang",
+        Msg::DoctorOrphaned => "von {} statt einer Shell gestartet, --env-fallback versuchen",
+        Msg::DoctorNamespace => {
+            "in einem verschachtelten PID-Namensraum, das /proc des Hosts mit --proc-root angeben"
+        }
+        Msg::DoctorNoVersion => "die Version von {} ist unbekannt, siehe shellver list",
+        Msg::DoctorShellUnset => "$SHELL ist nicht gesetzt",
+        Msg::DoctorShellDiffers => "$SHELL ist {}, nicht die laufende Shell",
+        Msg::Usage => concat!(
+            "Aufruf: shellver [Befehl] [Optionen]\n",
+            "\n",
+            "Befehle:\n",
+            "  detect               die aktuelle Shell und ihre Version ausgeben, der Standard\n",
+            "  ancestors            die Elternprozesskette und welche Glieder Shells sind\n",
+            "  completions <shell>  ein Vervollständigungsskript für bash, zsh oder fish\n",
+            "  doctor               Ursachen einer fehlschlagenden Erkennung finden\n",
+            "  dump                 ein geschwärzter Bericht der Kette für Fehlerberichte\n",
+            "  env                  das Ergebnis als Variablenzuweisungen für eval\n",
+            "  info                 die ausführliche Übersicht und aktive Umgebungen\n",
+            "  list                 unterstützte Shells, ihr Versionsbefehl und ihre Installation\n",
+            "  survey               alle Shell-Prozesse des Systems\n",
+            "  which <shell> [anf]  wo eine Shell installiert ist\n",
+            "  compare <a> <b>      Versionen und Fähigkeiten nebeneinander\n",
+            "  require [shell] <anf> erfolgreich, wenn die Shell-Version die Anforderung erfüllt\n",
+            "  selfcheck            prüfen, ob die Versionen aller Shells erkannt werden\n",
+            "\n",
+            "Optionen:\n",
+            "  -a, --all            alle Shells der Elternkette\n",
+            "  -o, --output <f>     plain, long, json, yaml, table, porcelain oder template\n",
+            "      --format <t>     mit Platzhaltern wie {name} {version} formatieren\n",
+            "      --template <t>   wie --format\n",
+            "      --shell <name>   die angegebene Shell statt einer erkannten ausgeben\n",
+            "      --color <when>   auto, always oder never, beachtet NO_COLOR\n",
+            "  -l, --long           wie --output long\n",
+            "      --json           wie --output json\n",
+            "      --porcelain      stabile, tabulatorgetrennte Zeilen für Skripte\n",
+            "      --semver         nur die Version als major.minor.patch\n",
+            "      --major          nur die Hauptversion\n",
+            "      --fingerprint    nur den Sitzungsschlüssel\n",
+            "      --watch[=<s>]    erneut ausgeben, sobald sich die Shell ändert\n",
+            "      --no-version     die Version auslassen, nur /proc wird gelesen\n",
+            "      --timeout <s>    Zeitlimit je Unterprozess in Sekunden\n",
+            "      --all-versions   alle Installationen bei which\n",
+            "      --proc-root <p>  ein anderes /proc durchlaufen\n",
+            "      --snapshot <d>   eine /proc-Kopie offline durchlaufen, ohne etwas auszuführen\n",
+            "      --ps <file>      eine ps -ef-Ausgabe durchlaufen, - für stdin\n",
+            "      --pid <pid>      den --ps-Durchlauf bei diesem Prozess beginnen\n",
+            "      --env-fallback   bei jedem Fehler auf $SHELL zurückgreifen\n",
+            "      --fallback <n>   diese Shell ausgeben, wenn die Erkennung scheitert\n",
+            "      --strict         nie auf $SHELL zurückgreifen, ohne Version scheitern\n",
+            "  -q, --quiet          keine Warnungen und kein Protokoll\n",
+            "  -v, --verbose        Versionsbefehle protokollieren, -vv auch Glieder und Lesezugriffe\n",
+            "  -h, --help           diese Hilfe ausgeben\n",
+        ),
+    }
+}
+
+const fn hu(msg: Msg) -> &'static str {
+    match msg {
+        Msg::Error => "hiba",
+        Msg::Warning => "figyelmeztetés",
+        Msg::UnknownCommand => "ismeretlen parancs: {}",
+        Msg::UnknownOption => "ismeretlen kapcsoló: {}",
+        Msg::UnexpectedArgument => "váratlan argumentum: {}",
+        Msg::MissingValue => "a(z) {} kapcsolónak érték kell",
+        Msg::UnknownFormat => "ismeretlen kimeneti formátum: {}",
+        Msg::InvalidTimeout => "érvénytelen időkorlát: {}",
+        Msg::InvalidPid => "érvénytelen PID: {}",
+        Msg::UnknownColor => "ismeretlen --color érték: {}",
+        Msg::TemplateRequired => "az --output template mellé --template kell",
+        Msg::Unparsed => "{}, nem értelmezhető verziókimenet:",
+        Msg::NoVersion => "a(z) {} verziója ismeretlen",
+        Msg::NoOutput => "(nincs kimenet)",
+        Msg::VersionFromPath => {
+            "a verzió a PATH-ban lévő programtól származik, nem a futó folyamattól"
+        }
+        Msg::EnvironmentShell => "a shell a $SHELL változóból származik, lehet, hogy nem ez fut",
+        Msg::CommExeMismatch => "a folyamat neve és programja eltér",
+        Msg::CompareNeedsTwo => "a compare parancsnak két shell kell",
+        Msg::WhichNeedsOne => {
+            "a which parancsnak egy shell kell, utána opcionálisan verziókövetelmény"
+        }
+        Msg::RequireNeedsOne => {
+            "a require parancsnak verziókövetelmény kell, előtte opcionálisan egy shell"
+        }
+        Msg::Unsatisfied => "{} nem teljesíti a követelményt",
+        Msg::CompletionsNeedsShell => "a completions parancsnak bash, zsh vagy fish kell",
+        Msg::NotInstalled => "a shell nincs telepítve: {}",
+        Msg::Yes => "igen",
+        Msg::No => "nem",
+        Msg::DoctorDetected => "felismerve: {}",
+        Msg::DoctorProcUnreadable => "a folyamatlánc nem olvasható: {}",
+        Msg::DoctorHidepid => {
+            "a /proc {} kapcsolóval van csatolva, más felhasználók folyamatai rejtettek"
+        }
+        Msg::DoctorHopLimit => "nincs shell {} szülőfolyamaton belül, a lánc túl hosszú",
+        Msg::DoctorOrphaned => "nem shell, hanem {} indította, próbáld az --env-fallback kapcsolót",
+        Msg::DoctorNamespace => {
+            "beágyazott PID-névtérben fut, add meg a gazdagép /proc könyvtárát a --proc-root kapcsolóval"
+        }
+        Msg::DoctorNoVersion => "a(z) {} verziója ismeretlen, lásd: shellver list",
+        Msg::DoctorShellUnset => "a $SHELL nincs beállítva",
+        Msg::DoctorShellDiffers => "a $SHELL értéke {}, ami nem a futó shell",
+        Msg::Usage => concat!(
+            "Használat: shellver [parancs] [kapcsolók]\n",
+            "\n",
+            "Parancsok:\n",
+            "  detect               a futó shell és verziója, ez az alapértelmezés\n",
+            "  ancestors            a szülőfolyamatok lánca és hogy melyik elem shell\n",
+            "  completions <shell>  kiegészítő szkript bash, zsh vagy fish számára\n",
+            "  doctor               a sikertelen felismerés okainak feltárása\n",
+            "  dump                 a lánc kitakart jelentése hibabejelentésekhez\n",
+            "  env                  az eredmény eval-lal futtatható változó-értékadásként\n",
+            "  info                 a részletes összegzés és az aktív környezetek\n",
+            "  list                 a támogatott shellek, verzióparancsuk és telepítésük\n",
+            "  survey               a rendszer összes shell folyamata\n",
+            "  which <shell> [köv]  hová van telepítve egy shell\n",
+            "  compare <a> <b>      verziók és képességek egymás mellett\n",
+            "  require [shell] <köv> sikeres, ha a shell verziója teljesíti a követelményt\n",
+            "  selfcheck            minden telepített shell verziója értelmezhető-e\n",
+            "\n",
+            "Kapcsolók:\n",
+            "  -a, --all            a szülőlánc összes shellje\n",
+            "  -o, --output <f>     plain, long, json, yaml, table, porcelain vagy template\n",
+            "      --format <t>     formázás helyőrzőkkel, pl. {name} {version}\n",
+            "      --template <t>   ugyanaz, mint a --format\n",
+            "      --shell <name>   a megadott shell kiírása felismerés helyett\n",
+            "      --color <when>   auto, always vagy never, a NO_COLOR-t figyelembe véve\n",
+            "  -l, --long           ugyanaz, mint az --output long\n",
+            "      --json           ugyanaz, mint az --output json\n",
+            "      --porcelain      stabil, tabulátorral tagolt sorok szkripteknek\n",
+            "      --semver         csak a verzió major.minor.patch alakban\n",
+            "      --major          csak a főverzió\n",
+            "      --fingerprint    csak a munkamenet ujjlenyomata\n",
+            "      --watch[=<s>]    újra kiírja, ha a shell megváltozik\n",
+            "      --no-version     verzió nélkül, gyorsan, csak a /proc olvasásával\n",
+            "      --timeout <s>    alfolyamatonkénti időkorlát másodpercben\n",
+            "      --all-versions   a which minden telepítést kiír\n",
+            "      --proc-root <p>  másik /proc bejárása\n",
+            "      --snapshot <d>   egy /proc másolat bejárása offline, futtatás nélkül\n",
+            "      --ps <file>      ps -ef kimenet bejárása, - a stdin-hez\n",
+            "      --pid <pid>      a --ps bejárás kezdése ennél a folyamatnál\n",
+            "      --env-fallback   bármilyen hibánál a $SHELL használata\n",
+            "      --fallback <n>   ezt a shellt írja ki, ha a felismerés sikertelen\n",
+            "      --strict         soha ne a $SHELL legyen a tartalék, verzió nélkül hiba\n",
+            "  -q, --quiet          nincs figyelmeztetés és napló\n",
+            "  -v, --verbose        a verzióparancsok naplózása, -vv a lánc elemeit és olvasásokat is\n",
+            "  -h, --help           ez a súgó\n",
+        ),
+    }
+}
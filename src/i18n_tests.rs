@@ -0,0 +1,37 @@
+mod tests {
+    use crate::i18n::*;
+
+    fn env(pairs: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |key| {
+            let pair = pairs.iter().find(|(k, _)| *k == key);
+            pair.map(|(_, value)| (*value).to_string())
+        }
+    }
+
+    #[test]
+    fn lang_from_precedence() {
+        assert_eq!(lang_from(env(&[])), Lang::En);
+        assert_eq!(lang_from(env(&[("LANG", "de_DE.UTF-8")])), Lang::De);
+        let vars = env(&[("LC_ALL", "hu_HU.UTF-8"), ("LANG", "de_DE.UTF-8")]);
+        assert_eq!(lang_from(vars), Lang::Hu);
+        let vars = env(&[("LC_ALL", ""), ("LC_MESSAGES", "C"), ("LANG", "de_DE")]);
+        assert_eq!(lang_from(vars), Lang::En);
+    }
+
+    #[test]
+    fn tables_keep_placeholders() {
+        for lang in [Lang::En, Lang::De, Lang::Hu] {
+            for msg in [
+                Msg::UnknownCommand,
+                Msg::MissingValue,
+                Msg::Unparsed,
+                Msg::Unsatisfied,
+                Msg::InvalidTimeout,
+                Msg::InvalidPid,
+                Msg::UnknownColor,
+            ] {
+                assert!(text(lang, msg).contains("{}"), "{lang:?} {msg:?}");
+            }
+        }
+    }
+}
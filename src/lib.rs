@@ -2,26 +2,576 @@
 //! Detect the current shell and its version on Linux by traversing `/proc`.
 //!
 //! The primary entry point is [`Shell::detect`], which walks the parent process
-//! chain to find a known shell and optionally extracts its version.
-use regex::Regex;
+//! chain to find a known shell and optionally extracts its version. Use a
+//! [`Detector`] to customize how detection is performed.
+//!
+//! The crate keeps no global state. Every setting lives on a [`Detector`] and
+//! every cache, such as the versions of nested shells, lives for a single
+//! call, so independent detectors can run side by side, e.g. in parallel
+//! tests or per tenant. The functions on [`Shell`] use a default detector.
+//! Versions are only kept across calls in a store set with
+//! [`Detector::cache`].
+pub mod cache;
+pub mod capability;
+pub mod parse;
+mod passwd;
+pub mod record;
+pub mod render;
+pub mod snapshot;
+
+use cache::CacheStore;
+use capability::Capability;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::env;
 use std::fs;
-use std::io;
-use std::process::Command;
+use std::io::{self, Read};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
-const SHELLS: [&str; 13] = [
-    "bash", "zsh", "sh", "tcsh", "csh", "ksh", "mksh", "fish", "dash", "nu", "elvish", "xonsh",
-    "pwsh",
+const SHELLS: [&str; 21] = [
+    "bash", "zsh", "sh", "tcsh", "csh", "ksh", "mksh", "fish", "dash", "ash", "nu", "elvish",
+    "xonsh", "pwsh", "osh", "ysh", "ion", "yash", "rc", "es", "murex",
 ];
-const SEMVER_PATTERN: &str = r"[0-9]+\.[0-9]+(?:\.[0-9]+)?";
-const MKSH_PATTERN: &str = r"R[0-9]+";
 const ARGS_VERSION: &[&str] = &["--version"];
-const ARGS_MKSH: &[&str] = &["-c", "printf %s \"$KSH_VERSION\""];
+// How each shell is asked for its version, for shells that do not answer
+// `--version` on stdout. `None` marks shells without any way to tell, e.g.
+// dash, whose version is only known to the package manager. The output is
+// parsed with the shell's rule in `parse::version`.
+const VERSION_COMMANDS: [(&str, Option<&[&str]>); 8] = [
+    ("dash", None),
+    ("ash", None),
+    // ksh93 prints `--version` to stderr and mksh has no such option, but
+    // every ksh sets `$KSH_VERSION`.
+    ("ksh", Some(&["-c", "printf %s \"$KSH_VERSION\""])),
+    ("mksh", Some(&["-c", "printf %s \"$KSH_VERSION\""])),
+    // The first line of the help text is the version banner.
+    (BUSYBOX, Some(&["--help"])),
+    // BSD csh has no version at all, tcsh installed as csh sets `$version`.
+    ("csh", Some(&["-c", "echo $version"])),
+    ("rc", Some(&["-c", "echo $version"])),
+    ("es", Some(&["-c", "echo <=$&version"])),
+];
+// How each shell prints its own version variable, for
+// `VersionQuery::Builtin`. Startup files are skipped where the shell allows
+// it, they may print too.
+const BUILTIN_COMMANDS: [(&str, &[&str]); 10] = [
+    (
+        "bash",
+        &["--noprofile", "--norc", "-c", "printf %s \"$BASH_VERSION\""],
+    ),
+    ("zsh", &["-f", "-c", "printf %s \"$ZSH_VERSION\""]),
+    ("ksh", &["-c", "printf %s \"$KSH_VERSION\""]),
+    ("mksh", &["-c", "printf %s \"$KSH_VERSION\""]),
+    ("yash", &["-c", "printf %s \"$YASH_VERSION\""]),
+    ("fish", &["-c", "echo $version"]),
+    ("tcsh", &["-f", "-c", "echo $version"]),
+    ("elvish", &["-norc", "-c", "echo $buildinfo[version]"]),
+    ("nu", &["--no-config-file", "-c", "version | get version"]),
+    (
+        "pwsh",
+        &[
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "$PSVersionTable.PSVersion.ToString()",
+        ],
+    ),
+];
+// Shells without a version command, whose version only the package manager
+// knows. Their package is named like the shell.
+const PACKAGED: [&str; 1] = ["dash"];
+// Package manager queries printing the installed version of a package, which
+// is appended. Each fails or prints no version where the manager is missing or
+// the package is not installed.
+const PACKAGE_QUERIES: [(&str, &[&str]); 4] = [
+    ("dpkg-query", &["-W", "-f=${Version}"]),
+    ("rpm", &["-q", "--qf", "%{VERSION}"]),
+    ("pacman", &["-Q"]),
+    ("apk", &["list", "--installed"]),
+];
+// Version variables that shells export, so the nearest shell of that kind
+// already told us its version through our environment.
+const VERSION_VARS: [(&str, &str); 3] = [
+    ("bash", "BASH_VERSION"),
+    ("zsh", "ZSH_VERSION"),
+    ("fish", "FISH_VERSION"),
+];
+// Shells without a version flag, whose version command differs between
+// implementations, e.g. plan9port's rc has no `$version`. When it fails the
+// version is unavailable rather than an error.
+const UNVERSIONED: [&str; 3] = ["csh", "rc", "es"];
+// Shell names also used by other programs, e.g. sysvinit's `/etc/init.d/rc`
+// script, which only count when the executable has the name too.
+const AMBIGUOUS: [&str; 1] = ["rc"];
+const PS: &str = "ps";
+const PROC_ROOT: &str = "/proc";
+const MAX_HOPS: u32 = 32;
+// Programs that commonly sit between a shell and its child without being one.
+// Parents that adopt orphaned processes besides pid 1, such as
+// `systemd --user`.
+const SESSION_MANAGERS: [&str; 2] = ["systemd", "init"];
+const WRAPPERS: [&str; 6] = ["sudo", "env", "script", "nohup", "setsid", "direnv"];
+const PASSWD: &str = "/etc/passwd";
+const GETENT: &str = "getent";
+// Shells databases in the order `getusershell` implementations consult them,
+// the second one being where BSD ports register their shells.
+const SHELLS_FILES: [&str; 2] = ["/etc/shells", "/usr/local/etc/shells"];
+// What `getusershell` assumes when no shells database exists.
+const DEFAULT_SHELLS: [&str; 2] = ["/bin/sh", "/bin/csh"];
+const BUSYBOX: &str = "busybox";
+// Multi-call binaries that provide shells as applets.
+const MULTICALL: [&str; 2] = [BUSYBOX, "toybox"];
+// Shells written in Python, whose process may be the interpreter.
+const PYTHON_SHELLS: [&str; 1] = ["xonsh"];
+// Restricted variants, named after the shell with an `r` in front.
+const RESTRICTED: [&str; 3] = ["rbash", "rksh", "rzsh"];
+const PREFIXES: [&str; 2] = ["/usr/local/bin", "/opt/homebrew/bin"];
+const NIX_PROFILE: &str = ".nix-profile/bin";
+// The kernel keeps `comm` to `TASK_COMM_LEN - 1` bytes, so a name of exactly
+// this length may have been cut off.
+const COMM_LEN: usize = 15;
+const TIMEOUT: Duration = Duration::from_secs(2);
+// Variables subprocesses keep: `PATH` to find programs, `HOME` for shells that
+// refuse to start without one, and `SystemRoot`, which Windows programs need.
+const KEPT_VARS: [&str; 3] = ["PATH", "HOME", "SystemRoot"];
+// Sets the log level of `Detector::log_level_from_env`.
+const LOG_VAR: &str = "SHELLVER_LOG";
+// Force the result of `Strategy::EnvOverride`.
+const SHELL_VAR: &str = "SHELLVER_SHELL";
+const VERSION_VAR: &str = "SHELLVER_VERSION";
+// Exported by `shellver env` along with `SHELLVER_VERSION`.
+const NAME_VAR: &str = "SHELLVER_NAME";
+// `USER_HZ`, the unit of times in `/proc`, which is 100 on every Linux target
+// and cannot be queried without libc.
+const CLK_TCK: u64 = 100;
 
 /// Information about the detected shell.
 #[derive(Debug)]
 pub struct Shell {
     name: String,
     version: Option<String>,
+    version_raw: Option<String>,
+    source: Source,
+    warnings: Vec<Warning>,
+    capabilities: Option<Vec<Capability>>,
+    pid: Option<u32>,
+    ppid: Option<u32>,
+    uid: Option<u32>,
+    path: Option<PathBuf>,
+    args: Vec<String>,
+    tty: Option<PathBuf>,
+    posix_mode: bool,
+    restricted: bool,
+    elevated_via: Option<Elevation>,
+    proc_dir: Option<PathBuf>,
+}
+
+/// A soft problem noticed during detection that did not prevent a result.
+///
+/// Every warning has a stable [code](Warning::code) that automation can
+/// match on, also across releases and languages:
+///
+/// | Code   | Warning                          |
+/// |--------|----------------------------------|
+/// | `W001` | [`Warning::CommExeMismatch`]     |
+/// | `W002` | [`Warning::VersionFromPath`]     |
+/// | `W003` | [`Warning::EnvironmentShell`]    |
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// `W002`: the version was queried from the binary found on `PATH`, which
+    /// may not be the one the running shell process was started from.
+    VersionFromPath,
+    /// `W003`: the shell was taken from `$SHELL` and may not be the running
+    /// shell.
+    EnvironmentShell,
+    /// `W001`: the process name in `comm` differs from its executable, e.g.
+    /// `bash` running `/usr/bin/zsh`.
+    CommExeMismatch,
+}
+
+impl Warning {
+    /// Returns the stable code of the warning, e.g. `W001`. Codes are never
+    /// reused for another warning.
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::CommExeMismatch => "W001",
+            Self::VersionFromPath => "W002",
+            Self::EnvironmentShell => "W003",
+        }
+    }
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::VersionFromPath => "version from PATH binary, not running process",
+            Self::EnvironmentShell => "shell from $SHELL, may not be the running shell",
+            Self::CommExeMismatch => "comm/exe mismatch",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// An environment activated in a shell, see [`Shell::activations`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Activation {
+    /// A Python virtual environment, the directory in `VIRTUAL_ENV`.
+    Venv(PathBuf),
+    /// A conda environment, the name in `CONDA_DEFAULT_ENV`.
+    Conda(String),
+    /// A directory whose `.envrc` direnv loaded, from `DIRENV_DIR`.
+    Direnv(PathBuf),
+}
+
+impl Activation {
+    /// Finds the activations recorded in environment variables, in the order
+    /// venv, conda, direnv.
+    ///
+    /// ```
+    /// use shellver::Activation;
+    ///
+    /// let vars = [("VIRTUAL_ENV".to_string(), "/src/app/.venv".to_string())];
+    /// assert_eq!(Activation::from_vars(vars), [Activation::Venv("/src/app/.venv".into())]);
+    /// ```
+    pub fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> Vec<Self> {
+        let vars: HashMap<String, String> = vars.into_iter().collect();
+        let var = |key: &str| vars.get(key).filter(|value| !value.is_empty());
+        let venv = var("VIRTUAL_ENV").map(|dir| Self::Venv(PathBuf::from(dir)));
+        let conda = var("CONDA_DEFAULT_ENV").map(|name| Self::Conda(name.clone()));
+        // direnv prefixes the directory with `-`.
+        let direnv =
+            var("DIRENV_DIR").map(|dir| Self::Direnv(PathBuf::from(dir.trim_start_matches('-'))));
+        [venv, conda, direnv].into_iter().flatten().collect()
+    }
+
+    /// Returns the lowercase kind used in output, e.g. `venv`.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Venv(_) => "venv",
+            Self::Conda(_) => "conda",
+            Self::Direnv(_) => "direnv",
+        }
+    }
+}
+
+impl std::fmt::Display for Activation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Venv(dir) | Self::Direnv(dir) => write!(f, "{}", dir.display()),
+            Self::Conda(name) => f.write_str(name),
+        }
+    }
+}
+
+/// A program that switches users, found between the caller and a shell, see
+/// [`Shell::elevated_via`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Elevation {
+    /// `sudo`, including `sudo -s` and `sudo -i`.
+    Sudo,
+    /// OpenBSD's `doas`.
+    Doas,
+    /// `su`, with or without `-`.
+    Su,
+}
+
+impl Elevation {
+    /// Returns the program name, e.g. `sudo`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Sudo => "sudo",
+            Self::Doas => "doas",
+            Self::Su => "su",
+        }
+    }
+
+    fn from_comm(comm: &str) -> Option<Self> {
+        match comm.trim_end() {
+            "sudo" => Some(Self::Sudo),
+            "doas" => Some(Self::Doas),
+            "su" => Some(Self::Su),
+            _ => None,
+        }
+    }
+}
+
+/// The syntax family of a shell, see [`Shell::family`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Family {
+    /// `sh` and its descendants: bash, zsh, ksh, mksh, dash, ash, yash,
+    /// busybox and Oils' bash-compatible `osh`.
+    Bourne,
+    /// csh and tcsh.
+    Csh,
+    /// PowerShell, `pwsh` on Linux, macOS and Windows.
+    PowerShell,
+    /// Shells with a syntax of their own, such as fish, nu, elvish, xonsh, ion,
+    /// murex, Plan 9's rc, es or Oils' `ysh`.
+    Other,
+}
+
+impl Family {
+    /// Returns the family of the shell called `name`.
+    #[must_use]
+    pub fn of(name: &str) -> Self {
+        match name {
+            "sh" | "bash" | "zsh" | "ksh" | "mksh" | "dash" | "ash" | "yash" | "osh" | BUSYBOX
+            | "toybox" => Self::Bourne,
+            "csh" | "tcsh" => Self::Csh,
+            "pwsh" | "powershell" => Self::PowerShell,
+            _ => Self::Other,
+        }
+    }
+
+    /// Returns a lowercase label, e.g. `bourne`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Bourne => "bourne",
+            Self::Csh => "csh",
+            Self::PowerShell => "powershell",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// How a shell numbers its releases, see [`Shell::version_scheme`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VersionScheme {
+    /// Dotted numbers, possibly with a suffix, e.g. bash's `5.2.15`, fish's
+    /// `3.7.1` or ksh2020's `2020.0.0`.
+    SemverLike,
+    /// mksh's single release number with an `R` prefix, e.g. `R59`. Releases
+    /// compare by the number.
+    MkshRelease,
+    /// A release date, e.g. `2012-08-01` or `20120801`.
+    DateBased,
+    /// Anything else, compared only for equality, e.g. ksh93's `93u+` or
+    /// ksh88's `88i`.
+    Raw,
+}
+
+impl VersionScheme {
+    /// Returns the scheme of `version` as reported by the shell `name`.
+    #[must_use]
+    pub fn of(name: &str, version: &str) -> Self {
+        let digits = |text: &str| !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit());
+        let dashed_date = || {
+            let parts: Vec<&str> = version.split('-').collect();
+            parts.len() == 3
+                && parts.iter().all(|part| digits(part))
+                && parts.iter().map(|part| part.len()).eq([4, 2, 2])
+        };
+        if name == "mksh" && version.strip_prefix('R').is_some_and(digits) {
+            Self::MkshRelease
+        } else if (digits(version) && version.len() == 8) || dashed_date() {
+            Self::DateBased
+        } else if version.split_once('.').is_some_and(|(major, rest)| {
+            digits(major) && rest.starts_with(|ch: char| ch.is_ascii_digit())
+        }) {
+            Self::SemverLike
+        } else {
+            Self::Raw
+        }
+    }
+
+    /// Returns a lowercase label, e.g. `semver`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::SemverLike => "semver",
+            Self::MkshRelease => "mksh-release",
+            Self::DateBased => "date",
+            Self::Raw => "raw",
+        }
+    }
+}
+
+/// The payload of the `NotFound` error returned when walking the parent
+/// process chain finds no shell, e.g. because the caller's shell exited and
+/// it was reparented to init.
+///
+/// ```no_run
+/// use shellver::{Shell, ShellNotFound};
+///
+/// if let Err(err) = Shell::detect() {
+///     let not_found = err.get_ref().and_then(|err| err.downcast_ref::<ShellNotFound>());
+///     if let Some(not_found) = not_found {
+///         eprintln!("walked {:?}, ended at {:?}", not_found.chain, not_found.end);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ShellNotFound {
+    /// The processes passed over, nearest first, as pid and command name.
+    pub chain: Vec<(u32, String)>,
+    /// Where the walk stopped.
+    pub end: ChainEnd,
+    /// Whether the caller runs in a nested PID namespace, e.g. a container,
+    /// whose chain ends at its own pid 1 while the shell that started it
+    /// lives outside. Walking the host's `/proc`, mounted into the container
+    /// and passed to [`Detector::proc_root`], may find it.
+    pub nested_pid_namespace: bool,
+}
+
+/// Where a walk of the parent process chain stopped, see [`ShellNotFound`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChainEnd {
+    /// Pid 1 or 0 was reached, so the caller was started by or reparented to
+    /// init.
+    Init,
+    /// A session manager such as `systemd --user` was reached, which adopts
+    /// orphaned processes.
+    SessionManager(String),
+    /// The hop limit was used up.
+    HopLimit,
+}
+
+impl ChainEnd {
+    /// Returns a stable snake case name, e.g. `hop_limit`.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Init => "init",
+            Self::SessionManager(_) => "session_manager",
+            Self::HopLimit => "hop_limit",
+        }
+    }
+}
+
+impl std::fmt::Display for ShellNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("shell not found")?;
+        match &self.end {
+            ChainEnd::Init => f.write_str(", reached init")?,
+            ChainEnd::SessionManager(name) => write!(f, ", reached session manager {name}")?,
+            ChainEnd::HopLimit => f.write_str(", hop limit reached")?,
+        }
+        for (i, (pid, comm)) in self.chain.iter().enumerate() {
+            let sep = if i == 0 { " after " } else { ", " };
+            write!(f, "{sep}{comm} ({pid})")?;
+        }
+        if self.nested_pid_namespace {
+            f.write_str("; running in a nested PID namespace, try the host's /proc as proc root")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ShellNotFound {}
+
+/// Where the detected shell was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// A running ancestor process found by walking `/proc`.
+    Process,
+    /// The `$SHELL` environment variable, which may differ from the running shell.
+    Environment,
+    /// The user's configured login shell in `/etc/passwd`.
+    Passwd,
+    /// A binary listed in `/etc/shells` or found on `PATH`, see [`Shell::installed`].
+    Installed,
+    /// Forced by `SHELLVER_SHELL`, see [`Strategy::EnvOverride`].
+    Override,
+    /// Named by the caller as a default, see [`Shell::named`].
+    Default,
+}
+
+impl Source {
+    /// Returns the lowercase name used in structured output.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Process => "process",
+            Self::Environment => "environment",
+            Self::Passwd => "passwd",
+            Self::Installed => "installed",
+            Self::Override => "override",
+            Self::Default => "default",
+        }
+    }
+}
+
+/// A way of finding the shell, tried in order by a [`Detector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Walk the parent process chain in `/proc`.
+    ProcWalk,
+    /// Read the `$SHELL` environment variable and query that binary's version.
+    EnvShell,
+    /// Look for a shell among all processes in the caller's session, not only
+    /// its ancestors, e.g. next to a forced command that `sshd` started. The
+    /// shell with the lowest pid wins.
+    Session,
+    /// Take the shell from `SHELLVER_SHELL`, a name such as `fish` or a path,
+    /// and its version from `SHELLVER_VERSION` or else the binary. As
+    /// `shellver env` exports `SHELLVER_VERSION` too, it is ignored while an
+    /// exported `SHELLVER_NAME` names another shell. An escape
+    /// hatch for CI and sandboxes where detection is impossible, tried first
+    /// when listed first. Passed over when `SHELLVER_SHELL` is unset, while an
+    /// unsupported shell in it fails detection.
+    EnvOverride,
+}
+
+/// How much a [`Detector`] reports on stderr about what it does, see
+/// [`Detector::log_level`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// Nothing.
+    #[default]
+    Off,
+    /// Every version command run and its raw output.
+    Info,
+    /// Also every hop of the parent chain and every file read.
+    Debug,
+}
+
+impl LogLevel {
+    /// Parses a level as given in `SHELLVER_LOG`: `off`, `info` or `debug`,
+    /// or `0` to `2`.
+    #[must_use]
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "off" | "0" => Some(Self::Off),
+            "info" | "1" => Some(Self::Info),
+            "debug" | "2" => Some(Self::Debug),
+            _ => None,
+        }
+    }
+}
+
+/// How a shell binary is asked for its version, see
+/// [`Detector::version_query`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VersionQuery {
+    /// Parse the banner of `--version` or the shell's equivalent.
+    #[default]
+    Banner,
+    /// Print the shell's own version variable, such as `$BASH_VERSION` or
+    /// `$ZSH_VERSION`, which needs no guessing at banner layouts. Shells
+    /// without one, or whose variable comes out empty, fall back to
+    /// [`Banner`](Self::Banner).
+    Builtin,
+    /// Ask nothing, for latency-critical callers such as prompts. No version
+    /// command runs and no binary is read, so detection only reads `/proc`
+    /// and versions are unknown.
+    Skip,
 }
 
 impl Shell {
@@ -30,29 +580,110 @@ impl Shell {
     /// Returns an error if the parent process chain cannot be read or if no
     /// known shell is found within the hop limit.
     pub fn detect() -> io::Result<Self> {
-        let read_file = |path: &str| -> io::Result<String> { fs::read_to_string(path) };
-        let run_cmd = |name: &str, args: &[&str]| -> io::Result<Vec<u8>> {
-            Ok(Command::new(name).args(args).output()?.stdout)
-        };
-        Self::detect_with(read_file, run_cmd)
+        Detector::new().detect()
     }
 
-    fn detect_with(read: ReadFn, run: RunFn) -> io::Result<Self> {
-        let mut pid = ppid_from_path_with("/proc/self/status", read)?;
-        let mut hops: u32 = 0;
-        while pid > 1 && hops < 32 {
-            let path = format!("/proc/{pid}/comm");
-            if let Some(name) = shell_from_pid_with(&path, read)? {
-                let version = shell_version_with(name, run)?;
-                let name = name.to_string();
-                return Ok(Self { name, version });
-            }
+    /// Returns the current user's configured login shell, which is not
+    /// necessarily the shell that is running.
+    ///
+    /// Like [`Shell::login_shell_of`], the entry is looked up with `getent
+    /// passwd` first and in `/etc/passwd` after.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user's entry cannot be read or if the login
+    /// shell is not a known shell, e.g. `/usr/sbin/nologin`.
+    pub fn detect_login_shell() -> io::Result<Self> {
+        Detector::new().detect_login_shell()
+    }
 
-            let path = format!("/proc/{pid}/status");
-            pid = ppid_from_path_with(&path, read)?;
-            hops += 1;
+    /// Returns the configured login shell of `user`, e.g. for admin tooling
+    /// reporting the default shell of every account.
+    ///
+    /// The entry is looked up with `getent passwd`, so NSS sources such as LDAP
+    /// are honored, with a plain `/etc/passwd` parse as fallback.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user does not exist or if the login shell is
+    /// not a known shell.
+    pub fn login_shell_of(user: &str) -> io::Result<Self> {
+        Detector::new().login_shell_of(user)
+    }
+
+    /// Returns every known shell installed on this machine, with versions,
+    /// sorted by name and then by version.
+    ///
+    /// Candidates are read from `/etc/shells` and `/usr/local/etc/shells`,
+    /// where BSD ports register theirs, and looked up on `PATH`; entries
+    /// resolving to the same binary, like `/bin/bash` and `/usr/bin/bash` on a
+    /// merged `/usr`, are reported once. See [`Detector::shells_files`] for
+    /// other locations.
+    #[must_use]
+    pub fn installed() -> Vec<Self> {
+        Detector::new().installed()
+    }
+
+    /// Returns every installation of the shell `name` with its version, in
+    /// `PATH` order followed by common prefixes outside it: `/usr/local/bin`,
+    /// `/opt/homebrew/bin` and `~/.nix-profile/bin`.
+    ///
+    /// The first entry from `PATH` is the binary scripts get. Paths resolving
+    /// to the same binary are reported once.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `name` is not a supported shell.
+    pub fn installations(name: &str) -> io::Result<Vec<Self>> {
+        Detector::new().installations(name)
+    }
+
+    /// Returns the version of a shell given by name, looked up on `PATH`, or
+    /// by path, without walking `/proc`.
+    ///
+    /// ```no_run
+    /// use shellver::Shell;
+    ///
+    /// let zsh = Shell::version_of("zsh")?;
+    /// let fish = Shell::version_of("/usr/local/bin/fish")?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the version command cannot be run, e.g. because the
+    /// shell is not installed.
+    pub fn version_of(name_or_path: &str) -> io::Result<Option<String>> {
+        Detector::new().version_of(name_or_path)
+    }
+
+    #[must_use]
+    /// Returns a shell known only by `name`, without version or path, e.g. a
+    /// default to report when detection fails. Its source is
+    /// [`Source::Default`].
+    pub fn named(name: impl Into<String>) -> Self {
+        Self::new(name, Source::Default)
+    }
+
+    fn new(name: impl Into<String>, source: Source) -> Self {
+        Self {
+            name: name.into(),
+            version: None,
+            version_raw: None,
+            source,
+            warnings: Vec::new(),
+            capabilities: None,
+            pid: None,
+            ppid: None,
+            uid: None,
+            path: None,
+            args: Vec::new(),
+            tty: None,
+            posix_mode: false,
+            restricted: false,
+            elevated_via: None,
+            proc_dir: None,
         }
-        Err(io::Error::new(io::ErrorKind::NotFound, "shell not found"))
     }
 
     #[must_use]
@@ -67,68 +698,2346 @@ impl Shell {
         self.version.clone()
     }
 
-    /// Returns the list of supported shell names.
     #[must_use]
-    pub const fn supported_shells() -> &'static [&'static str] {
-        &SHELLS
+    /// Returns the untouched output the version was parsed from, e.g.
+    /// `GNU bash, version 5.2.15(1)-release (x86_64-pc-linux-gnu)`, to show or
+    /// log the precise string.
+    ///
+    /// It is unknown when the version came from a [cache](Detector::cache),
+    /// the environment, the package manager or the binary itself.
+    pub fn version_raw(&self) -> Option<&str> {
+        self.version_raw.as_deref()
     }
-}
 
-type ReadFn = fn(&str) -> io::Result<String>;
-type RunFn = fn(&str, &[&str]) -> io::Result<Vec<u8>>;
+    #[must_use]
+    /// Returns the pre-release or build suffix that followed the version in
+    /// its [raw output](Self::version_raw), e.g. `(1)-release` for bash
+    /// `5.2.15(1)-release` or `+archlinux1` for elvish `0.21.0+archlinux1`,
+    /// so distro builds can be told apart.
+    pub fn version_extra(&self) -> Option<String> {
+        parse::version_extra(&self.name, self.version_raw.as_deref()?.as_bytes())
+    }
 
-fn ppid_from_path_with(path: &str, read: ReadFn) -> io::Result<u32> {
-    let text = read(path)?;
-    ppid_from_text(&text)
-}
+    #[must_use]
+    /// Returns the version normalized to `major.minor.patch`, padding missing
+    /// components with `0` and dropping suffixes, e.g. `5.9` becomes `5.9.0`
+    /// and `0.21.0+archlinux1` becomes `0.21.0`.
+    pub fn semver(&self) -> Option<String> {
+        let [major, minor, patch] = semver_parts(self.version.as_deref()?)?;
+        Some(format!("{major}.{minor}.{patch}"))
+    }
+
+    /// Returns whether the version meets a requirement such as `>=4.4`,
+    /// `^5.1` or `>=3.2, <6`, with comparators joined by commas and read the
+    /// way Cargo reads them: a bare version means `^`, `~5.1` allows patch
+    /// releases and partial versions such as `<6` pad with zeros.
+    ///
+    /// An unknown version satisfies no requirement but `*`.
+    ///
+    /// ```no_run
+    /// use shellver::Shell;
+    ///
+    /// let shell = Shell::detect()?;
+    /// if shell.name() == "bash" && !shell.satisfies(">=4.4")? {
+    ///     eprintln!("bash 4.4 or newer is required");
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidInput` error if the requirement cannot be parsed.
+    pub fn satisfies(&self, requirement: &str) -> io::Result<bool> {
+        let ranges = requirement_ranges(requirement)?;
+        let version = self.version.as_deref().and_then(semver_parts);
+        Ok(ranges.iter().all(|(low, high)| {
+            version.map_or_else(
+                || low.is_none() && high.is_none(),
+                |version| {
+                    low.is_none_or(|low| version >= low) && high.is_none_or(|high| version < high)
+                },
+            )
+        }))
+    }
+
+    #[must_use]
+    /// Returns how the version is numbered, so it can be interpreted right,
+    /// e.g. [`VersionScheme::MkshRelease`] for mksh's `R59`.
+    pub fn version_scheme(&self) -> Option<VersionScheme> {
+        let version = self.version.as_deref()?;
+        Some(VersionScheme::of(&self.name, version))
+    }
+
+    #[must_use]
+    /// Returns the major version number, e.g. `5` for bash `5.2.15`.
+    pub fn major(&self) -> Option<u64> {
+        semver_parts(self.version.as_deref()?).map(|[major, ..]| major)
+    }
 
-fn ppid_from_text(text: &str) -> io::Result<u32> {
-    for line in text.lines() {
-        if let Some(ppid) = line.strip_prefix("PPid:") {
-            let val = ppid
-                .trim()
-                .parse::<u32>()
-                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "PPid parse failed"))?;
-            return Ok(val);
+    #[must_use]
+    /// Returns the process id of a shell found among running processes.
+    pub const fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    #[must_use]
+    /// Returns the parent process id of a shell found among running processes,
+    /// if it could be read.
+    pub const fn ppid(&self) -> Option<u32> {
+        self.ppid
+    }
+
+    #[must_use]
+    /// Returns the command line of a running shell including `argv[0]`, e.g.
+    /// `["-bash"]` for a login shell or `["sh", "-c", "make"]`. Empty when the
+    /// shell was not found among processes or its command line is unreadable.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    #[must_use]
+    /// Returns whether the running shell is a login shell, either started by
+    /// `login` or `sshd` with a `-` in front of `argv[0]`, e.g. `-bash`, or
+    /// with `-l` or `--login`.
+    pub fn is_login(&self) -> bool {
+        let Some((argv0, flags)) = self.args.split_first() else {
+            return false;
+        };
+        argv0.starts_with('-') || flags.iter().any(|arg| arg == "-l" || arg == "--login")
+    }
+
+    #[must_use]
+    /// Returns whether the running shell is interactive.
+    ///
+    /// Explicit flags win: `-i` makes a shell interactive, `-c` or a script
+    /// operand makes it non-interactive, like PowerShell's `-NonInteractive`.
+    /// Otherwise a shell is interactive when its stdin is a terminal, which is
+    /// how shells decide this themselves.
+    pub fn is_interactive(&self) -> bool {
+        let Some((_, flags)) = self.args.split_first() else {
+            return false;
+        };
+        let (options, operand) = split_options(flags);
+        if options
+            .iter()
+            .any(|option| option.eq_ignore_ascii_case("-noninteractive"))
+        {
+            return false;
         }
+        if self.has_option(&options, 'i', "--interactive") {
+            return true;
+        }
+        if self.has_option(&options, 'c', "--command") || operand.is_some() {
+            return false;
+        }
+        self.tty.is_some()
     }
-    Err(io::Error::new(io::ErrorKind::NotFound, "PPid not found"))
-}
 
-fn shell_from_pid_with(path: &str, read: ReadFn) -> io::Result<Option<&'static str>> {
-    let text = read(path)?;
-    let shell = SHELLS.iter().copied().find(|sh| text.trim_end() == *sh);
-    Ok(shell)
-}
+    // Whether `options` contain `-<short>` or `long`. Bourne and csh shells
+    // also take short flags clustered, like `bash -ic`, while other shells
+    // have single-dash words of their own, like `pwsh -noprofile`.
+    fn has_option(&self, options: &[&str], short: char, long: &str) -> bool {
+        let clusters = matches!(self.family(), Family::Bourne | Family::Csh);
+        options.iter().any(|option| {
+            option.eq_ignore_ascii_case(long)
+                || option.strip_prefix('-').is_some_and(|cluster| {
+                    if clusters {
+                        cluster.bytes().all(|byte| byte.is_ascii_lowercase())
+                            && cluster.contains(short)
+                    } else {
+                        cluster.len() == 1 && cluster.starts_with(short)
+                    }
+                })
+        })
+    }
 
-fn shell_version_with(name: &str, run: RunFn) -> io::Result<Option<String>> {
-    let Some(args) = shell_args(name) else {
-        return Ok(None);
-    };
-    let out = run(name, args)?;
-    let text = String::from_utf8(out)
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "non utf8 bytes"))?;
-    let re = Regex::new(version_pattern(name)).unwrap();
-    Ok(re.find(&text).map(|m| m.as_str().to_string()))
-}
+    // How the shell was invoked, judged from its command line.
+    pub(crate) fn mode(&self) -> Option<&'static str> {
+        let (_, flags) = self.args.split_first()?;
+        let (options, _) = split_options(flags);
+        Some(if self.is_login() {
+            "login"
+        } else if self.has_option(&options, 'c', "--command") {
+            "command"
+        } else if self.is_interactive() {
+            "interactive"
+        } else {
+            "default"
+        })
+    }
 
-fn shell_args(name: &str) -> Option<&'static [&'static str]> {
-    match name {
-        // Dash doesn't have version option or any other argument to get its version.
-        // One way to retrieve the version is using the system package manager.
-        "dash" => None,
-        "mksh" => Some(ARGS_MKSH),
-        _ => Some(ARGS_VERSION),
+    #[must_use]
+    /// Returns a short key for this shell session, 16 hex digits hashed from
+    /// the executable, version, invocation mode and terminal, e.g. for other
+    /// tools to cache per-session state. It is stable across processes and
+    /// releases, so two shells agree on it exactly when those fields agree.
+    pub fn fingerprint(&self) -> String {
+        let path = self
+            .path
+            .as_ref()
+            .map_or_else(|| self.name.clone(), |path| path.display().to_string());
+        let tty = self.tty.as_ref().map(|tty| tty.display().to_string());
+        let fields = [
+            path,
+            self.version.clone().unwrap_or_default(),
+            self.mode().unwrap_or_default().to_string(),
+            tty.unwrap_or_default(),
+        ];
+        format!("{:016x}", fnv1a(fields.join("\0").as_bytes()))
     }
-}
 
-fn version_pattern(name: &str) -> &'static str {
-    if name == "mksh" {
-        MKSH_PATTERN
-    } else {
-        SEMVER_PATTERN
+    #[must_use]
+    /// Returns whether the running shell emulates a POSIX `sh`, e.g. bash
+    /// installed as `/bin/sh` or started with `--posix`.
+    ///
+    /// A process called `sh` is reported under the name of the binary that
+    /// implements it, such as `dash`, `bash` or `busybox`.
+    pub const fn posix_mode(&self) -> bool {
+        self.posix_mode
     }
-}
 
-#[cfg(test)]
-mod lib_tests;
+    #[must_use]
+    /// Returns the syntax family of the shell, e.g. [`Family::PowerShell`]
+    /// for `pwsh`.
+    pub fn family(&self) -> Family {
+        Family::of(&self.name)
+    }
+
+    #[must_use]
+    /// Returns whether the shell is restricted, i.e. invoked as `rbash`,
+    /// `rksh` or `rzsh` or, for running shells, started with `-r` or
+    /// `--restricted`. Such a shell refuses to `cd`, to change `PATH` or to
+    /// run commands containing a `/`.
+    ///
+    /// Restricted shells are reported under the name of the shell they
+    /// restrict, e.g. `bash` for `rbash`.
+    pub const fn is_restricted(&self) -> bool {
+        self.restricted
+    }
+
+    #[must_use]
+    /// Returns the program that switched users between the caller and this
+    /// shell, e.g. [`Elevation::Sudo`] for a command run with `sudo` from the
+    /// user's shell. The shell then belongs to another user than the caller,
+    /// so its `$HOME` and configuration are not the caller's.
+    ///
+    /// The nearest such program is reported. It is only known for shells found
+    /// by walking the process chain.
+    pub const fn elevated_via(&self) -> Option<Elevation> {
+        self.elevated_via
+    }
+
+    #[must_use]
+    /// Returns the terminal on the running shell's stdin, e.g. `/dev/pts/3`,
+    /// if it is attached to one.
+    pub fn tty(&self) -> Option<&Path> {
+        self.tty.as_deref()
+    }
+
+    #[must_use]
+    /// Returns the user id owning the shell, from `/proc/<pid>/status` for
+    /// running shells or from the passwd entry for login shells.
+    pub const fn uid(&self) -> Option<u32> {
+        self.uid
+    }
+
+    /// Returns the name of the user owning the shell, asking `getent passwd`
+    /// first and falling back to `/etc/passwd`, like [`Shell::login_shell_of`].
+    ///
+    /// Comparing it to the current user reveals shells started through `su`
+    /// or `sudo -s`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the owner is unknown or has no passwd entry.
+    pub fn user(&self) -> io::Result<String> {
+        self.user_with(LIVE)
+    }
+
+    fn user_with(&self, sys: Sys) -> io::Result<String> {
+        let unknown = || io::Error::new(io::ErrorKind::NotFound, "shell owner unknown");
+        let uid = self.uid.ok_or_else(unknown)?;
+        passwd_lookup_with(&uid.to_string(), PASSWD, sys, |text| {
+            passwd::entry_by_uid(text, uid).map(|entry| entry.name.to_string())
+        })
+    }
+
+    /// Reads the environment block of a running shell from
+    /// `/proc/<pid>/environ`, e.g. to learn its `TERM` or `VIRTUAL_ENV`.
+    ///
+    /// The block is read on every call, only when asked for, since it may hold
+    /// secrets and is usually readable only by the shell's owner. It reflects
+    /// the environment the shell was started with, not later `export`s.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the shell was not found among running processes or
+    /// if its environment cannot be read.
+    pub fn environ(&self) -> io::Result<HashMap<String, String>> {
+        self.environ_with(LIVE)
+    }
+
+    fn environ_with(&self, sys: Sys) -> io::Result<HashMap<String, String>> {
+        let path = self.proc_file("environ")?;
+        let bytes = sys.read_bytes(&path)?;
+        Ok(parse::environ(&bytes))
+    }
+
+    /// Returns the Python venv, conda environment and direnv directory that
+    /// were active when the shell started, read from its [`Shell::environ`],
+    /// e.g. to avoid telling the user to activate them twice.
+    ///
+    /// An environment activated inside the shell only shows up in processes
+    /// it starts afterwards. A program run from the shell sees the current
+    /// state in its own environment, see [`Activation::from_vars`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the shell's environment cannot be read.
+    pub fn activations(&self) -> io::Result<Vec<Activation>> {
+        self.activations_with(LIVE)
+    }
+
+    fn activations_with(&self, sys: Sys) -> io::Result<Vec<Activation>> {
+        Ok(Activation::from_vars(self.environ_with(sys)?))
+    }
+
+    /// Returns the current working directory of a running shell, resolved
+    /// from `/proc/<pid>/cwd` on every call since it changes with each `cd`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the shell was not found among running processes or
+    /// if its working directory cannot be read.
+    pub fn cwd(&self) -> io::Result<PathBuf> {
+        self.cwd_with(LIVE)
+    }
+
+    fn cwd_with(&self, sys: Sys) -> io::Result<PathBuf> {
+        let cwd = sys.read_link(&self.proc_file("cwd")?)?;
+        Ok(strip_deleted(&cwd).0)
+    }
+
+    /// Returns whether the running shell leads its session, i.e. its pid
+    /// equals the session id in `/proc/<pid>/stat`. That holds for the shell
+    /// a terminal emulator, `sshd` or tmux started, and not for subshells
+    /// nested in it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the shell was not found among running processes or
+    /// if its stat file cannot be read.
+    pub fn is_session_leader(&self) -> io::Result<bool> {
+        self.is_session_leader_with(LIVE)
+    }
+
+    fn is_session_leader_with(&self, sys: Sys) -> io::Result<bool> {
+        let session = parse::stat_session(sys.read(&self.proc_file("stat")?)?.as_bytes())?;
+        Ok(self.pid == Some(session))
+    }
+
+    /// Returns when the running shell was started, from the `starttime` in
+    /// `/proc/<pid>/stat` and the system uptime. A changed value for the same
+    /// pid means the shell was restarted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the shell was not found among running processes or
+    /// if its stat file or `/proc/uptime` cannot be read.
+    pub fn started_at(&self) -> io::Result<SystemTime> {
+        self.started_at_with(LIVE, SystemTime::now())
+    }
+
+    fn started_at_with(&self, sys: Sys, now: SystemTime) -> io::Result<SystemTime> {
+        let ticks = parse::stat_start_ticks(sys.read(&self.proc_file("stat")?)?.as_bytes())?;
+        let root = self.proc_dir.as_deref().and_then(Path::parent);
+        let uptime = root.map_or_else(|| PathBuf::from(PROC_ROOT), Path::to_path_buf);
+        let uptime = format!("{}/uptime", uptime.display());
+        let uptime = parse::uptime(sys.read(&uptime)?.as_bytes())?;
+        let age = uptime.saturating_sub(Duration::from_millis(ticks * 1000 / CLK_TCK));
+        now.checked_sub(age)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "start time out of range"))
+    }
+
+    fn proc_file(&self, file: &str) -> io::Result<String> {
+        let not_process = || io::Error::new(io::ErrorKind::NotFound, "shell is not a process");
+        let dir = self.proc_dir.as_ref().ok_or_else(not_process)?;
+        Ok(format!("{}/{file}", dir.display()))
+    }
+
+    #[must_use]
+    /// Returns the shell executable, e.g. `/usr/bin/zsh` resolved from
+    /// `/proc/<pid>/exe`, if known.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    #[must_use]
+    /// Returns whether the shell was found in the process chain or in `$SHELL`.
+    pub const fn source(&self) -> Source {
+        self.source
+    }
+
+    #[must_use]
+    /// Returns the capabilities of this shell, probed empirically when
+    /// [`Detector::probe`] is enabled and taken from the static table otherwise.
+    pub fn capabilities(&self) -> Vec<Capability> {
+        self.capabilities
+            .clone()
+            .unwrap_or_else(|| capability::of(&self.name))
+    }
+
+    #[must_use]
+    /// Returns the soft problems noticed while detecting this shell.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    #[must_use]
+    /// Returns the idiomatic command to make this shell re-read its
+    /// configuration, e.g. for a final "now run: ..." hint of an installer.
+    pub fn reload_command(&self) -> String {
+        let cmd = match self.name.as_str() {
+            "bash" => "exec bash -l",
+            "zsh" => "source ~/.zshrc",
+            "tcsh" => "source ~/.tcshrc",
+            "csh" => "source ~/.cshrc",
+            "sh" | "dash" | "ash" => ". ~/.profile",
+            "yash" => ". ~/.yashrc",
+            "pwsh" => ". $PROFILE",
+            name => return format!("exec {name}"),
+        };
+        cmd.to_string()
+    }
+
+    #[must_use]
+    /// Returns how a program should run a command in this shell
+    /// non-interactively: the executable, or the name to look up on `PATH`
+    /// when the path is unknown, and the flags that skip user configuration,
+    /// ending with the one that takes the command.
+    ///
+    /// For `pwsh` that is `["pwsh", "-NoProfile", "-NonInteractive",
+    /// "-Command"]`, for bash `["/usr/bin/bash", "--noprofile", "--norc",
+    /// "-c"]`.
+    ///
+    /// ```no_run
+    /// use shellver::Shell;
+    /// use std::process::Command;
+    ///
+    /// let argv = Shell::detect()?.recommended_invocation();
+    /// let status = Command::new(&argv[0]).args(&argv[1..]).arg("make").status()?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn recommended_invocation(&self) -> Vec<String> {
+        // fish reads its configuration even for `-c` and learned to skip it
+        // in 3.6.
+        let fish_no_config = self
+            .version
+            .as_deref()
+            .and_then(semver_parts)
+            .is_some_and(|[major, minor, _]| (major, minor) >= (3, 6));
+        let flags: &[&str] = match self.name.as_str() {
+            "bash" => &["--noprofile", "--norc", "-c"],
+            "zsh" | "tcsh" | "csh" => &["-f", "-c"],
+            "fish" if fish_no_config => &["--no-config", "-c"],
+            "pwsh" => &["-NoProfile", "-NonInteractive", "-Command"],
+            "nu" => &["--no-config-file", "-c"],
+            "elvish" => &["-norc", "-c"],
+            "xonsh" => &["--no-rc", "-c"],
+            // Multi-call binaries run their shell as an applet.
+            name if MULTICALL.contains(&name) => &["sh", "-c"],
+            _ => &["-c"],
+        };
+        let program = self
+            .path
+            .as_ref()
+            .map_or_else(|| self.name.clone(), |path| path.display().to_string());
+        std::iter::once(program)
+            .chain(flags.iter().map(ToString::to_string))
+            .collect()
+    }
+
+    /// Returns the list of supported shell names.
+    #[must_use]
+    pub const fn supported_shells() -> &'static [&'static str] {
+        &SHELLS
+    }
+
+    /// Returns the command line run to ask the shell `name` for its version,
+    /// e.g. `bash --version`, or `None` for shells without one, such as dash.
+    #[must_use]
+    pub fn version_command(name: &str) -> Option<String> {
+        let quote = |arg: &str| {
+            if arg.contains(' ') {
+                format!("'{arg}'")
+            } else {
+                arg.to_string()
+            }
+        };
+        let args = shell_args(name)?.iter().map(|arg| quote(arg));
+        Some(
+            std::iter::once(name.to_string())
+                .chain(args)
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+
+    /// Runs the version command of every supported shell found on `PATH` and
+    /// reports the raw output next to the parsed version.
+    ///
+    /// Shells without a version command or whose command cannot be run are
+    /// skipped.
+    #[must_use]
+    pub fn self_check() -> Vec<SelfCheck> {
+        Self::self_check_with(|name| find_in_path(name).is_some(), run_cmd)
+    }
+
+    fn self_check_with(installed: fn(&str) -> bool, run: RunFn) -> Vec<SelfCheck> {
+        SHELLS
+            .iter()
+            .copied()
+            .filter(|name| installed(name))
+            .filter_map(|name| {
+                let raw = version_output_with(name, name, VersionQuery::Banner, run).ok()??;
+                let version = parse::version(name, raw.as_bytes());
+                Some(SelfCheck { name, raw, version })
+            })
+            .collect()
+    }
+}
+
+/// Configurable shell detection.
+///
+/// ```no_run
+/// use shellver::Detector;
+///
+/// let shell = Detector::new().proc_root("/host/proc").detect()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Detector {
+    proc_root: PathBuf,
+    strategies: Vec<Strategy>,
+    allow_exec: bool,
+    probe: bool,
+    timeout: Duration,
+    skip: Vec<String>,
+    cache: Option<(Arc<dyn CacheStore>, Duration)>,
+    passwd: PathBuf,
+    shells_files: Vec<PathBuf>,
+    version_query: VersionQuery,
+    version_timeout: Option<Duration>,
+    package_versions: bool,
+    log_level: LogLevel,
+}
+
+impl Default for Detector {
+    fn default() -> Self {
+        Self {
+            proc_root: PathBuf::from(PROC_ROOT),
+            strategies: vec![Strategy::ProcWalk],
+            allow_exec: true,
+            probe: false,
+            timeout: TIMEOUT,
+            skip: WRAPPERS.map(String::from).to_vec(),
+            cache: None,
+            passwd: PathBuf::from(PASSWD),
+            shells_files: SHELLS_FILES.map(PathBuf::from).to_vec(),
+            version_query: VersionQuery::Banner,
+            version_timeout: None,
+            package_versions: false,
+            log_level: LogLevel::Off,
+        }
+    }
+}
+
+impl Detector {
+    /// Creates a detector reading the live `/proc`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads process information from `root` instead of `/proc`, e.g. a host
+    /// mount like `/host/proc` inside a privileged container or a fixture tree.
+    #[must_use]
+    pub fn proc_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.proc_root = root.into();
+        self
+    }
+
+    /// Analyzes a captured process tree at `root` offline, such as a copy of
+    /// `/proc` or one written by [`snapshot::from_ps`]: reads it like
+    /// [`proc_root`](Self::proc_root) but spawns nothing, so versions are
+    /// unknown and nothing of the live system leaks in.
+    #[must_use]
+    pub fn snapshot(self, root: impl Into<PathBuf>) -> Self {
+        self.proc_root(root)
+            .allow_exec(false)
+            .version_query(VersionQuery::Skip)
+    }
+
+    /// Sets the strategies to try, in order. The first one that finds a shell
+    /// wins. Defaults to [`Strategy::ProcWalk`] only.
+    ///
+    /// ```
+    /// use shellver::{Detector, Strategy};
+    ///
+    /// let detector = Detector::new().strategies([Strategy::ProcWalk, Strategy::EnvShell]);
+    /// ```
+    #[must_use]
+    pub fn strategies(mut self, strategies: impl IntoIterator<Item = Strategy>) -> Self {
+        self.strategies = strategies.into_iter().collect();
+        self
+    }
+
+    /// Allows or forbids spawning subprocesses such as version commands, `ps`
+    /// and capability probes. When forbidden, detection only reads files and
+    /// versions are reported as unknown. Defaults to `true`.
+    #[must_use]
+    pub const fn allow_exec(mut self, allow: bool) -> Self {
+        self.allow_exec = allow;
+        self
+    }
+
+    /// Verifies [`Shell::capabilities`] by running tiny feature-test snippets
+    /// in POSIX-like shells instead of trusting the static table. Has no
+    /// effect when subprocesses are forbidden. Defaults to `false`.
+    #[must_use]
+    pub const fn probe(mut self, probe: bool) -> Self {
+        self.probe = probe;
+        self
+    }
+
+    /// Bounds how long a single probe subprocess may run before it is killed.
+    /// Defaults to two seconds.
+    #[must_use]
+    pub const fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the process names passed over without using up the hop limit,
+    /// such as wrappers between the shell and the caller. Defaults to `sudo`,
+    /// `env`, `script`, `nohup`, `setsid` and `direnv`.
+    ///
+    /// ```
+    /// use shellver::Detector;
+    ///
+    /// let detector = Detector::new().skip_processes(["sudo", "doas", "tmux: server"]);
+    /// ```
+    #[must_use]
+    pub fn skip_processes<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.skip = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Keeps the versions of shell binaries in `store` for `ttl`, so later
+    /// calls and other detectors sharing the store, such as the next
+    /// [`survey`](Self::survey), skip the version commands. See the
+    /// [`cache`] module for the built-in stores.
+    #[must_use]
+    pub fn cache(mut self, store: Arc<dyn CacheStore>, ttl: Duration) -> Self {
+        self.cache = Some((store, ttl));
+        self
+    }
+
+    /// Reads user entries from `path` instead of `/etc/passwd` when `getent`
+    /// does not know the user, e.g. an `/etc/passwd` mounted from the host.
+    #[must_use]
+    pub fn passwd_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.passwd = path.into();
+        self
+    }
+
+    /// Sets the shells databases listing [installed](Self::installed) shells,
+    /// all of which are read. Defaults to `/etc/shells` and
+    /// `/usr/local/etc/shells`, where BSD ports register their shells.
+    ///
+    /// ```
+    /// use shellver::Detector;
+    ///
+    /// let detector = Detector::new().shells_files(["/host/etc/shells"]);
+    /// ```
+    #[must_use]
+    pub fn shells_files<P: Into<PathBuf>>(mut self, paths: impl IntoIterator<Item = P>) -> Self {
+        self.shells_files = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets how shell binaries are asked for their versions. Defaults to
+    /// [`VersionQuery::Banner`].
+    ///
+    /// ```
+    /// use shellver::{Detector, VersionQuery};
+    ///
+    /// let detector = Detector::new().version_query(VersionQuery::Builtin);
+    /// ```
+    #[must_use]
+    pub const fn version_query(mut self, query: VersionQuery) -> Self {
+        self.version_query = query;
+        self
+    }
+
+    /// Reports what detection does on stderr, each line prefixed with
+    /// `shellver:`. Defaults to [`LogLevel::Off`].
+    #[must_use]
+    pub const fn log_level(mut self, level: LogLevel) -> Self {
+        self.log_level = level;
+        self
+    }
+
+    /// Sets the [log level](Self::log_level) given in `SHELLVER_LOG`, e.g.
+    /// `SHELLVER_LOG=debug`, leaving it unchanged when the variable is unset
+    /// or invalid. Detectors never read it on their own.
+    #[must_use]
+    pub fn log_level_from_env(self) -> Self {
+        match env_var(LOG_VAR).and_then(|level| LogLevel::parse(&level)) {
+            Some(level) => self.log_level(level),
+            None => self,
+        }
+    }
+
+    /// Bounds how long a version command may run before it is killed and
+    /// the version is reported as unknown, e.g. for a binary on a hung
+    /// network mount. Defaults to two seconds.
+    #[must_use]
+    pub const fn version_timeout(mut self, timeout: Duration) -> Self {
+        self.version_timeout = Some(timeout);
+        self
+    }
+
+    /// Asks the system package manager, `dpkg-query`, `rpm`, `pacman` or
+    /// `apk`, for the version of shells that cannot report their own, such
+    /// as dash. The packaged version belongs to the system's binary, which is
+    /// usually, but not necessarily, the one running. Defaults to `false`.
+    #[must_use]
+    pub const fn package_versions(mut self, enable: bool) -> Self {
+        self.package_versions = enable;
+        self
+    }
+
+    // Shells without a version command get the version embedded in their
+    // binary, or else the packaged one if enabled.
+    fn fallback_version_with(&self, mut shell: Shell, sys: Sys) -> Shell {
+        if shell.version.is_some()
+            || shell_args(&shell.name).is_some()
+            || self.version_query == VersionQuery::Skip
+        {
+            return shell;
+        }
+        let binary = shell
+            .path
+            .clone()
+            .or_else(|| find_in_path_with(&shell.name, sys));
+        shell.version = binary
+            .and_then(|path| sys.read_bytes(&path.to_string_lossy()).ok())
+            .and_then(|binary| parse::embedded_version(&shell.name, &binary));
+        if shell.version.is_none()
+            && self.package_versions
+            && PACKAGED.contains(&shell.name.as_str())
+        {
+            shell.version = package_version_with(&shell.name, self.version_run(sys));
+        }
+        shell
+    }
+
+    // Version commands run through `sys.run` unless a timeout was set.
+    fn version_run(&self, sys: Sys) -> impl Run {
+        let timeout = self.version_timeout;
+        let log_level = self.log_level;
+        move |name: &str, args: &[&str]| {
+            let out = timeout.map_or_else(
+                || (sys.run)(name, args),
+                |timeout| (sys.run_timeout)(name, args, timeout),
+            );
+            if log_level >= LogLevel::Info {
+                log(&format!("run {name} {}", args.join(" ")));
+                match &out {
+                    Ok(out) => log(&format!("output {:?}", String::from_utf8_lossy(out))),
+                    Err(err) => log(&format!("failed: {err}")),
+                }
+            }
+            out
+        }
+    }
+
+    fn versions(&self) -> Versions<'_> {
+        Versions {
+            query: self.version_query,
+            seen: HashMap::new(),
+            store: self
+                .cache
+                .as_ref()
+                .map(|(store, ttl)| (store.as_ref(), *ttl)),
+        }
+    }
+
+    /// # Errors
+    ///
+    /// Returns the error of the first strategy if none of them finds a shell,
+    /// e.g. if the parent process chain cannot be read or if no known shell is
+    /// found within the hop limit.
+    pub fn detect(&self) -> io::Result<Shell> {
+        self.detect_with(LIVE)
+    }
+
+    fn detect_with(&self, sys: Sys) -> io::Result<Shell> {
+        let sys = self.restrict(sys);
+        let mut first_err = None;
+        for strategy in &self.strategies {
+            let res = match strategy {
+                Strategy::ProcWalk => self.walk_with(sys),
+                Strategy::EnvShell => {
+                    env_shell_with(self.version_query, self.version_run(sys), sys)
+                }
+                Strategy::Session => self.session_shell_with(sys),
+                Strategy::EnvOverride => {
+                    env_override_with(self.version_query, self.version_run(sys), sys)
+                }
+            };
+            match res {
+                Ok(shell) => {
+                    let shell = self.fallback_version_with(shell, sys);
+                    return Ok(self.probe_with(shell, sys));
+                }
+                // An unset override must not hide why detection failed, and
+                // a set but unsupported one is a mistake, not a miss.
+                Err(err) if *strategy == Strategy::EnvOverride => match err.kind() {
+                    io::ErrorKind::NotFound => {}
+                    io::ErrorKind::InvalidInput => return Err(err),
+                    _ => _ = first_err.get_or_insert(err),
+                },
+                Err(err) => _ = first_err.get_or_insert(err),
+            }
+        }
+        Err(first_err.unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "shell not found")))
+    }
+
+    // Swaps the subprocess runners for ones that spawn nothing and produce no
+    // output when exec is forbidden, so versions and `ps` lookups come up empty.
+    fn restrict(&self, sys: Sys) -> Sys {
+        let sys = self.trace(sys);
+        if self.allow_exec {
+            return sys;
+        }
+        Sys {
+            run: |_, _| Ok(Vec::new()),
+            run_timeout: |_, _, _| Ok(Vec::new()),
+            ..sys
+        }
+    }
+
+    // Has the readers log every path at the debug level.
+    fn trace(&self, sys: Sys) -> Sys {
+        Sys {
+            trace: self.log_level >= LogLevel::Debug,
+            ..sys
+        }
+    }
+
+    fn probe_with(&self, mut shell: Shell, sys: Sys) -> Shell {
+        if !self.probe || !self.allow_exec || !capability::probeable(&shell.name) {
+            return shell;
+        }
+        let supported = |cap: &Capability| {
+            let out = (sys.run_timeout)(&shell.name, &["-c", cap.snippet()], self.timeout);
+            out.is_ok_and(|out| out.trim_ascii() == b"ok")
+        };
+        shell.capabilities = Some(Capability::ALL.into_iter().filter(supported).collect());
+        shell
+    }
+
+    fn walk_with(&self, sys: Sys) -> io::Result<Shell> {
+        let (mut shells, not_found) = self.walk_all_with(sys, true)?;
+        shells
+            .pop()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, not_found))
+    }
+
+    /// Returns every shell in the parent process chain, nearest first, e.g.
+    /// `[zsh, bash]` when a zsh was started from a login bash.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parent process chain cannot be read.
+    pub fn detect_all(&self) -> io::Result<Vec<Shell>> {
+        self.detect_all_with(LIVE)
+    }
+
+    fn detect_all_with(&self, sys: Sys) -> io::Result<Vec<Shell>> {
+        let sys = self.restrict(sys);
+        let (shells, _) = self.walk_all_with(sys, false)?;
+        Ok(shells
+            .into_iter()
+            .map(|shell| self.probe_with(self.fallback_version_with(shell, sys), sys))
+            .collect())
+    }
+
+    /// Returns every process in the parent chain up to init, nearest first,
+    /// with the shell each one was recognized as, e.g. to see why detection
+    /// picked a shell or found none. The first recognized hop is what
+    /// [`detect`](Self::detect) reports.
+    ///
+    /// The walk stops where [`detect_all`](Self::detect_all) would and asks
+    /// no shell for its version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parent process chain cannot be read.
+    pub fn ancestors(&self) -> io::Result<Vec<Ancestor>> {
+        self.ancestors_with(LIVE)
+    }
+
+    fn ancestors_with(&self, sys: Sys) -> io::Result<Vec<Ancestor>> {
+        let sys = self.restrict(sys);
+        let status = sys.read(&self.proc_path("self", "status"))?;
+        let mut pid = parse::status_ppid(status.as_bytes())?;
+        let mut ancestors = Vec::new();
+        let (mut hops, mut skipped) = (0, 0);
+        while pid > 1 && hops < MAX_HOPS {
+            let comm = self.comm_with(pid, sys)?;
+            let shell = self.shell_named_with(pid, &comm, sys);
+            let comm = comm.trim_end().to_string();
+            if shell.is_none() && self.skip.contains(&comm) && skipped < MAX_HOPS {
+                skipped += 1;
+            } else {
+                hops += 1;
+            }
+            ancestors.push(Ancestor { pid, comm, shell });
+            pid = self.ppid_from_pid_with(pid, sys)?;
+        }
+        Ok(ancestors)
+    }
+
+    // Versions are cached per shell name, so nested shells of the same kind
+    // only spawn one version command. Next to the shells, returns how the
+    // walk ended, for the error when there are none.
+    fn walk_all_with(&self, sys: Sys, first_only: bool) -> io::Result<(Vec<Shell>, ShellNotFound)> {
+        let mut versions = self.versions();
+        let mut shells: Vec<Shell> = Vec::new();
+        let mut child_arg_start = None;
+        let status = sys.read(&self.proc_path("self", "status"))?;
+        let mut pid = parse::status_ppid(status.as_bytes())?;
+        let nspid = parse::status_nspid(status.as_bytes());
+        let nested_pid_namespace = nspid.is_ok_and(|pids| pids.len() > 1);
+        let mut hops: u32 = 0;
+        // Skipped wrappers get a budget of their own, so a cycle in a broken
+        // proc tree still ends.
+        let mut skipped: u32 = 0;
+        let mut elevated_via = None;
+        let mut chain = Vec::new();
+        while pid > 1 && hops < MAX_HOPS {
+            let comm = self.comm_with(pid, sys)?;
+            let name = self.shell_named_with(pid, &comm, sys);
+            if self.log_level >= LogLevel::Debug {
+                log(&format!(
+                    "hop {pid} {:?} is {}",
+                    comm.trim_end(),
+                    name.unwrap_or("no shell")
+                ));
+            }
+            let Some(name) = name else {
+                chain.push((pid, comm.trim_end().to_string()));
+                elevated_via = elevated_via.or_else(|| Elevation::from_comm(&comm));
+                let wrapper = self.skip.iter().any(|skip| skip == comm.trim_end());
+                if wrapper && skipped < MAX_HOPS {
+                    skipped += 1;
+                } else {
+                    hops += 1;
+                }
+                pid = self.ppid_from_pid_with(pid, sys)?;
+                continue;
+            };
+            let (mut shell, program) = self.process_shell_with(name, pid, sys);
+            shell.elevated_via = elevated_via;
+            // Only the nearest shell passed its variables on to us, outer ones
+            // may have been overwritten by it.
+            let inherited = (shells.is_empty() && self.version_query != VersionQuery::Skip)
+                .then(|| inherited_version_with(name, sys.var))
+                .flatten();
+            if inherited.is_some() {
+                shell.version = inherited;
+            } else {
+                query_version_with(&mut shell, &program, self.version_run(sys), &mut versions)?;
+            }
+            // Under `hidepid` the status file is unreadable, fall back to the
+            // slower sources.
+            let parent = shell
+                .ppid
+                .map_or_else(|| self.ppid_from_pid_with(pid, sys), Ok);
+            shell.ppid = parent.as_ref().ok().copied();
+            if first_only {
+                shells.push(shell);
+                break;
+            }
+            // A subshell such as `( ... )` or `$( ... )` is a fork of its
+            // parent shell, not a shell the user started, so only the parent
+            // is reported.
+            let arg_start = self.arg_start_with(pid, sys);
+            let forked = shells.last().is_some_and(|child| {
+                child.ppid == Some(pid) && child.path == shell.path && child.args == shell.args
+            });
+            if forked && arg_start.is_some() && child_arg_start == arg_start {
+                shells.pop();
+            }
+            child_arg_start = arg_start;
+            shells.push(shell);
+
+            pid = parent?;
+            hops += 1;
+        }
+        let end = match chain.last() {
+            Some((_, comm)) if SESSION_MANAGERS.contains(&comm.as_str()) => {
+                ChainEnd::SessionManager(comm.clone())
+            }
+            _ if pid <= 1 => ChainEnd::Init,
+            _ => ChainEnd::HopLimit,
+        };
+        let not_found = ShellNotFound {
+            chain,
+            end,
+            nested_pid_namespace,
+        };
+        Ok((shells, not_found))
+    }
+
+    // Describes the shell process `pid` and picks the binary to query for its
+    // version: the one behind `/proc/<pid>/exe` when it is readable and still
+    // exists, otherwise the shell name looked up on `PATH`.
+    fn process_shell_with(&self, name: &'static str, pid: u32, sys: Sys) -> (Shell, Program) {
+        let mut shell = Shell::new(name, Source::Process);
+        shell.pid = Some(pid);
+        shell.proc_dir = Some(self.proc_root.join(pid.to_string()));
+        if let Ok(status) = sys.read(&self.proc_path(pid, "status")) {
+            shell.uid = parse::status_uid(status.as_bytes()).ok();
+            shell.ppid = parse::status_ppid(status.as_bytes()).ok();
+        }
+        shell.args = self.args_with(pid, sys);
+        let stdin = sys.read_link(&self.proc_path(pid, "fd/0")).ok();
+        shell.tty = stdin.filter(|stdin| is_tty(stdin));
+        let exe = sys.read_link(&self.proc_path(pid, "exe")).ok();
+        let (path, deleted) = exe.map_or((None, false), |exe| {
+            let (path, deleted) = strip_deleted(&exe);
+            (Some(path), deleted)
+        });
+        let live = path.clone().filter(|_| !deleted);
+        shell.path = path;
+        let exe_name = live.as_deref().and_then(file_name);
+        let implementation = exe_name.and_then(implementation_from_name);
+        // The interpreter runs the shell's script, which is what answers
+        // `--version`. Without one, as for `python -m xonsh`, the shell's
+        // name is looked up on `PATH`.
+        let script = python_shell(&shell.args)
+            .filter(|(python, _)| *python == name && exe_name.is_some_and(is_python))
+            .map(|(_, script)| script.map(str::to_string));
+        match implementation {
+            // `sh` is only an alias, report what implements it.
+            Some(real) if name == "sh" && real != "sh" => {
+                shell.name = real.to_string();
+                shell.posix_mode = matches!(real, "bash" | "zsh");
+            }
+            // Applets keep their name, e.g. `ash` run by busybox.
+            Some(real) if MULTICALL.contains(&real) => {}
+            _ if script.is_some() => {}
+            _ if exe_name.is_some_and(|exe| implementation.unwrap_or(exe) != name) => {
+                shell.warnings.push(Warning::CommExeMismatch);
+            }
+            _ => {}
+        }
+        shell.posix_mode |= has_posix_flag(&shell.args);
+        let argv0 = shell.args.first().map(|arg| arg.trim_start_matches('-'));
+        shell.restricted = [argv0, exe_name]
+            .into_iter()
+            .flatten()
+            .any(|name| is_restricted_name(Path::new(name)))
+            || has_restricted_flag(&shell.args);
+        let live = match script {
+            Some(script) => {
+                let script = script.map(PathBuf::from);
+                shell.path = script.clone().or(shell.path);
+                script
+            }
+            None => live,
+        };
+        let program = live.as_deref().and_then(Path::to_str).map_or_else(
+            || Program {
+                name,
+                path: name.to_string(),
+                on_path: true,
+            },
+            |path| Program {
+                name: implementation.unwrap_or(name),
+                path: path.to_string(),
+                on_path: false,
+            },
+        );
+        (shell, program)
+    }
+
+    /// Returns every running shell process on the system visible in `/proc`,
+    /// sorted by owning user id and then by pid.
+    ///
+    /// Processes that exit or become unreadable while scanning are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the proc root cannot be listed.
+    pub fn survey(&self) -> io::Result<Vec<Shell>> {
+        self.survey_with(LIVE)
+    }
+
+    fn session_shell_with(&self, sys: Sys) -> io::Result<Shell> {
+        let own = sys.read(&self.proc_path("self", "stat"))?;
+        let session = parse::stat_session(own.as_bytes())?;
+        let root = self.proc_root.to_string_lossy();
+        let mut found: Option<(u32, &'static str)> = None;
+        for entry in (sys.list)(&root)? {
+            let Ok(pid) = entry.parse::<u32>() else {
+                continue;
+            };
+            // Processes exit while listing, and the session is fixed once
+            // read, so unreadable entries are simply skipped.
+            let Ok(stat) = sys.read(&self.proc_path(pid, "stat")) else {
+                continue;
+            };
+            if parse::stat_session(stat.as_bytes()).ok() != Some(session) {
+                continue;
+            }
+            let comm = parse::stat_comm(stat.as_bytes()).ok();
+            let name = comm.and_then(|comm| self.shell_named_with(pid, &comm, sys));
+            if let Some(name) = name.filter(|_| found.is_none_or(|(min, _)| pid < min)) {
+                found = Some((pid, name));
+            }
+        }
+        let not_found = || io::Error::new(io::ErrorKind::NotFound, "shell not found in session");
+        let (pid, name) = found.ok_or_else(not_found)?;
+        let (mut shell, program) = self.process_shell_with(name, pid, sys);
+        let run = self.version_run(sys);
+        query_version_with(&mut shell, &program, run, &mut self.versions())?;
+        Ok(shell)
+    }
+
+    fn survey_with(&self, sys: Sys) -> io::Result<Vec<Shell>> {
+        let sys = self.restrict(sys);
+        let root = self.proc_root.to_string_lossy();
+        let mut versions = self.versions();
+        let mut shells = Vec::new();
+        for entry in (sys.list)(&root)? {
+            let Ok(pid) = entry.parse::<u32>() else {
+                continue;
+            };
+            let comm = sys.read(&self.proc_path(pid, "comm"));
+            let name = comm
+                .ok()
+                .and_then(|comm| self.shell_named_with(pid, &comm, sys));
+            let Some(name) = name else {
+                continue;
+            };
+            let (mut shell, program) = self.process_shell_with(name, pid, sys);
+            // Versions are best effort in a survey, a failing binary must not
+            // hide the process.
+            _ = query_version_with(&mut shell, &program, self.version_run(sys), &mut versions);
+            shells.push(self.fallback_version_with(shell, sys));
+        }
+        shells.sort_by_key(|shell| (shell.uid.is_none(), shell.uid, shell.pid));
+        Ok(shells)
+    }
+
+    /// Returns the installed shells, see [`Shell::installed`]. Versions are
+    /// left out when exec is disallowed.
+    #[must_use]
+    pub fn installed(&self) -> Vec<Shell> {
+        let sys = self.restrict(LIVE);
+        let shells = installed_with(
+            &self.shells_files,
+            self.version_query,
+            self.version_run(sys),
+            sys,
+        );
+        shells
+            .into_iter()
+            .map(|shell| self.fallback_version_with(shell, sys))
+            .collect()
+    }
+
+    /// Returns every installation of the shell `name`, see
+    /// [`Shell::installations`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `name` is not a supported shell.
+    pub fn installations(&self, name: &str) -> io::Result<Vec<Shell>> {
+        let sys = self.restrict(LIVE);
+        let shells = installations_with(name, self.version_query, self.version_run(sys), sys)?;
+        Ok(shells
+            .into_iter()
+            .map(|shell| self.fallback_version_with(shell, sys))
+            .collect())
+    }
+
+    /// Returns the version of a shell given by name or path, see
+    /// [`Shell::version_of`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the version command cannot be run.
+    pub fn version_of(&self, name_or_path: &str) -> io::Result<Option<String>> {
+        let run = self.version_run(self.restrict(LIVE));
+        version_of_with(name_or_path, self.version_query, run)
+    }
+
+    /// Returns the current user's login shell, see [`Shell::detect_login_shell`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user's entry cannot be read or if the login
+    /// shell is not a known shell.
+    pub fn detect_login_shell(&self) -> io::Result<Shell> {
+        self.detect_login_shell_with(LIVE)
+    }
+
+    fn detect_login_shell_with(&self, sys: Sys) -> io::Result<Shell> {
+        let sys = self.restrict(sys);
+        let uid = parse::status_uid(sys.read(&self.proc_path("self", "status"))?.as_bytes())?;
+        let shell = passwd_lookup_with(&uid.to_string(), &self.passwd_path(), sys, |text| {
+            passwd::entry_by_uid(text, uid)
+                .map(|entry| login_shell_with(&entry, self.version_query, self.version_run(sys)))
+        })??;
+        Ok(self.fallback_version_with(shell, sys))
+    }
+
+    /// Returns the login shell of `user`, see [`Shell::login_shell_of`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user does not exist or if the login shell is
+    /// not a known shell.
+    pub fn login_shell_of(&self, user: &str) -> io::Result<Shell> {
+        self.login_shell_of_with(user, LIVE)
+    }
+
+    fn login_shell_of_with(&self, user: &str, sys: Sys) -> io::Result<Shell> {
+        let sys = self.restrict(sys);
+        let shell = passwd_lookup_with(user, &self.passwd_path(), sys, |text| {
+            passwd::entry_by_name(text, user)
+                .map(|entry| login_shell_with(&entry, self.version_query, self.version_run(sys)))
+        })??;
+        Ok(self.fallback_version_with(shell, sys))
+    }
+
+    fn passwd_path(&self) -> String {
+        self.passwd.to_string_lossy().into_owned()
+    }
+
+    /// Walks the parent processes, nearest first, and returns the value of
+    /// `var` from the first environment block that defines it.
+    ///
+    /// This recovers variables such as `SSH_AUTH_SOCK` or `TMUX` that a wrapper
+    /// stripped from the current environment. Ancestors whose environment
+    /// cannot be read, e.g. because they belong to another user, are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parent process chain cannot be read.
+    pub fn find_env_in_ancestry(&self, var: &str) -> io::Result<Option<String>> {
+        self.find_env_in_ancestry_with(var, LIVE)
+    }
+
+    fn find_env_in_ancestry_with(&self, var: &str, sys: Sys) -> io::Result<Option<String>> {
+        let sys = self.restrict(sys);
+        let mut pid = ppid_from_path_with(&self.proc_path("self", "status"), sys)?;
+        let mut hops: u32 = 0;
+        while pid > 0 && hops < MAX_HOPS {
+            let environ = sys.read(&self.proc_path(pid, "environ"));
+            if let Some(value) = environ
+                .ok()
+                .and_then(|text| parse::environ_var(text.as_bytes(), var))
+            {
+                return Ok(Some(value));
+            }
+            if pid == 1 {
+                break;
+            }
+
+            pid = self.ppid_from_pid_with(pid, sys)?;
+            hops += 1;
+        }
+        Ok(None)
+    }
+
+    fn proc_path(&self, pid: impl std::fmt::Display, file: &str) -> String {
+        format!("{}/{pid}/{file}", self.proc_root.display())
+    }
+
+    // On a `hidepid` mounted /proc the `status` and `comm` files of other
+    // processes may be unreadable. Fall back to `stat`, then to the `ps`
+    // backend, and report the original error only when every source fails.
+    fn ppid_from_pid_with(&self, pid: u32, sys: Sys) -> io::Result<u32> {
+        let err = match ppid_from_path_with(&self.proc_path(pid, "status"), sys) {
+            Err(err) if is_hidden(&err) => err,
+            res => return res,
+        };
+        let stat = sys
+            .read(&self.proc_path(pid, "stat"))
+            .and_then(|text| parse::stat_ppid(text.as_bytes()));
+        stat.or_else(|_| ppid_from_ps_with(pid, sys.run))
+            .map_err(|_| err)
+    }
+
+    fn comm_with(&self, pid: u32, sys: Sys) -> io::Result<String> {
+        match sys.read(&self.proc_path(pid, "comm")) {
+            Err(err) if is_hidden(&err) => {
+                let stat = sys
+                    .read(&self.proc_path(pid, "stat"))
+                    .and_then(|text| parse::stat_comm(text.as_bytes()));
+                stat.or_else(|_| comm_from_ps_with(pid, sys.run))
+                    .map_err(|_| err)
+            }
+            res => res,
+        }
+    }
+
+    fn shell_named_with(&self, pid: u32, comm: &str, sys: Sys) -> Option<&'static str> {
+        let shell = shell_from_comm(comm, || self.args_with(pid, sys));
+        if let Some(name) = shell {
+            let exe = AMBIGUOUS
+                .contains(&name)
+                .then(|| self.exe_name_with(pid, sys))
+                .flatten();
+            return shell.filter(|_| exe.is_none_or(|exe| exe == name));
+        }
+        let args = self.args_with(pid, sys);
+        if let Some((shell, _)) = python_shell(&args) {
+            return Some(shell);
+        }
+        let argv0 = args
+            .first()
+            .map(|arg| Path::new(arg.trim_start_matches('-')));
+        let argv0 = argv0.and_then(shell_from_file);
+        if comm.trim_end().len() == COMM_LEN {
+            return self.shell_from_exe_with(pid, sys).or(argv0);
+        }
+        // A shell run through a renamed link such as `mytool -> bash`, with
+        // neither name known. A known `argv[0]` with an unknown name is an
+        // interpreter running a script, which is not the user's shell.
+        if argv0.is_none() {
+            return self.shell_from_exe_with(pid, sys);
+        }
+        None
+    }
+
+    // The file name of a process' executable, if readable.
+    fn exe_name_with(&self, pid: u32, sys: Sys) -> Option<String> {
+        let exe = sys.read_link(&self.proc_path(pid, "exe")).ok()?;
+        program_name(&strip_deleted(&exe).0).map(str::to_string)
+    }
+
+    // Names a process by its executable, for a `comm` that was truncated,
+    // e.g. `powershell-prev`, or renamed.
+    fn shell_from_exe_with(&self, pid: u32, sys: Sys) -> Option<&'static str> {
+        let exe = sys.read_link(&self.proc_path(pid, "exe")).ok();
+        let exe = exe.map(|exe| strip_deleted(&exe).0);
+        exe.as_deref().and_then(shell_from_file)
+    }
+
+    // Where `argv` lives in the process' memory. A fork keeps its parent's
+    // address space and so the same value, while `exec` lays it out anew.
+    // The kernel shows 0 to readers who may not trace the process.
+    fn arg_start_with(&self, pid: u32, sys: Sys) -> Option<u64> {
+        let stat = sys.read_bytes(&self.proc_path(pid, "stat")).ok()?;
+        parse::stat_arg_start(&stat)
+            .ok()
+            .filter(|&start| start != 0)
+    }
+
+    fn args_with(&self, pid: u32, sys: Sys) -> Vec<String> {
+        let cmdline = sys.read_bytes(&self.proc_path(pid, "cmdline"));
+        cmdline
+            .map(|bytes| parse::cmdline(&bytes))
+            .unwrap_or_default()
+    }
+}
+
+/// One process of the parent chain, see [`Detector::ancestors`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ancestor {
+    pid: u32,
+    comm: String,
+    shell: Option<&'static str>,
+}
+
+impl Ancestor {
+    #[must_use]
+    /// Returns the process id.
+    pub const fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    #[must_use]
+    /// Returns the process name from `/proc/<pid>/comm`.
+    pub fn comm(&self) -> &str {
+        &self.comm
+    }
+
+    #[must_use]
+    /// Returns the shell the process was recognized as, or `None` for
+    /// anything else, such as a terminal or a wrapper.
+    pub const fn shell(&self) -> Option<&'static str> {
+        self.shell
+    }
+}
+
+/// Outcome of parsing one shell's version output, see [`Shell::self_check`].
+#[derive(Debug)]
+pub struct SelfCheck {
+    name: &'static str,
+    raw: String,
+    version: Option<String>,
+}
+
+impl SelfCheck {
+    #[must_use]
+    /// Returns the checked shell name.
+    pub const fn name(&self) -> &str {
+        self.name
+    }
+
+    #[must_use]
+    /// Returns the untouched output of the version command.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    #[must_use]
+    /// Returns the parsed version, or `None` if the parser failed.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+}
+
+type ReadFn = fn(&str) -> io::Result<String>;
+type RunFn = fn(&str, &[&str]) -> io::Result<Vec<u8>>;
+type VarFn = fn(&str) -> Option<String>;
+type RunTimeoutFn = fn(&str, &[&str], Duration) -> io::Result<Vec<u8>>;
+
+/// Runs a version command, like [`RunFn`] but also taking closures that apply
+/// a detector's version timeout.
+trait Run: Fn(&str, &[&str]) -> io::Result<Vec<u8>> + Copy {}
+
+impl<F: Fn(&str, &[&str]) -> io::Result<Vec<u8>> + Copy> Run for F {}
+
+/// The system calls detection depends on, swapped out by tests.
+#[derive(Clone, Copy)]
+struct Sys {
+    read: ReadFn,
+    run: RunFn,
+    run_timeout: RunTimeoutFn,
+    var: VarFn,
+    list: fn(&str) -> io::Result<Vec<String>>,
+    read_bytes: fn(&str) -> io::Result<Vec<u8>>,
+    read_link: fn(&str) -> io::Result<PathBuf>,
+    is_exec: fn(&Path) -> bool,
+    canonicalize: fn(&Path) -> io::Result<PathBuf>,
+    /// Logs every file read, set at [`LogLevel::Debug`].
+    trace: bool,
+}
+
+impl Sys {
+    fn read(&self, path: &str) -> io::Result<String> {
+        self.log("read", path);
+        (self.read)(path)
+    }
+
+    fn read_bytes(&self, path: &str) -> io::Result<Vec<u8>> {
+        self.log("read", path);
+        (self.read_bytes)(path)
+    }
+
+    fn read_link(&self, path: &str) -> io::Result<PathBuf> {
+        self.log("readlink", path);
+        (self.read_link)(path)
+    }
+
+    fn log(&self, op: &str, path: &str) {
+        if self.trace {
+            log(&format!("{op} {path}"));
+        }
+    }
+}
+
+const LIVE: Sys = Sys {
+    read: read_file,
+    run: run_cmd,
+    run_timeout: run_cmd_timeout,
+    var: env_var,
+    list: list_dir,
+    read_bytes: read_prefix,
+    read_link: |path| fs::read_link(path),
+    is_exec: is_executable,
+    canonicalize: |path| fs::canonicalize(path),
+    trace: false,
+};
+
+fn log(msg: &str) {
+    eprintln!("shellver: {msg}");
+}
+
+fn read_file(path: &str) -> io::Result<String> {
+    fs::read_to_string(path)
+}
+
+// Reads no more than any parser looks at, the binary scan looking furthest, so
+// scanning a large binary stays cheap.
+fn read_prefix(path: &str) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let limit = parse::MAX_BINARY as u64;
+    fs::File::open(path)?.take(limit).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+// Every command is bounded by `TIMEOUT`, a hanging `--version` must not hang
+// detection.
+fn run_cmd(name: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+    run_cmd_timeout(name, args, TIMEOUT)
+}
+
+// Runs a command with a null stdin and a minimal environment in the C locale,
+// so banners come out untranslated and unaffected by the user's shell setup,
+// killing it once `timeout` elapses. The output is drained on separate
+// threads so a chatty child cannot block on a full pipe while we wait for it.
+//
+// Some programs only write to stderr, like ksh93's `--version` or tcsh builds
+// reporting their version as an error, so stderr is returned when stdout is
+// blank.
+//
+// The child never sees the terminal, so it cannot change its modes, and it
+// stays in our foreground process group, so a Ctrl-C stops it together with
+// us. Every other way out, errors and panics included, goes through
+// `Supervised`, which kills and reaps it.
+fn run_cmd_timeout(name: &str, args: &[&str], timeout: Duration) -> io::Result<Vec<u8>> {
+    let mut command = Command::new(name);
+    command
+        .args(args)
+        .env_clear()
+        .env("LC_ALL", "C")
+        .env("LANG", "C");
+    for key in KEPT_VARS {
+        if let Some(value) = env::var_os(key) {
+            command.env(key, value);
+        }
+    }
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut child = Supervised(child);
+    let stdout = child.0.stdout.take();
+    let stdout = drain(stdout.ok_or_else(|| io::Error::other("no stdout"))?);
+    let stderr = child.0.stderr.take();
+    let stderr = drain(stderr.ok_or_else(|| io::Error::other("no stderr"))?);
+    let deadline = Instant::now() + timeout;
+    while child.0.try_wait()?.is_none() {
+        if Instant::now() >= deadline {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "command timed out"));
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    let join = |reader: thread::JoinHandle<io::Result<Vec<u8>>>| {
+        reader
+            .join()
+            .map_err(|_| io::Error::other("output reader panicked"))?
+    };
+    let out = join(stdout)?;
+    let err = join(stderr)?;
+    Ok(if out.trim_ascii().is_empty() {
+        err
+    } else {
+        out
+    })
+}
+
+fn drain(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut out = Vec::new();
+        pipe.read_to_end(&mut out).map(|_| out)
+    })
+}
+
+/// A child process that is killed and reaped when dropped, so no code path
+/// leaves a running or zombie process behind.
+struct Supervised(Child);
+
+impl Drop for Supervised {
+    fn drop(&mut self) {
+        // Both fail harmlessly if the child has already been reaped.
+        _ = self.0.kill();
+        _ = self.0.wait();
+    }
+}
+
+fn list_dir(path: &str) -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(path)? {
+        names.push(entry?.file_name().to_string_lossy().into_owned());
+    }
+    Ok(names)
+}
+
+fn env_var(key: &str) -> Option<String> {
+    env::var(key).ok()
+}
+
+fn env_shell_with(query: VersionQuery, run: impl Run, sys: Sys) -> io::Result<Shell> {
+    let not_found = || io::Error::new(io::ErrorKind::NotFound, "shell not found in $SHELL");
+    let path = (sys.var)("SHELL").filter(|path| !path.is_empty());
+    let path = path.ok_or_else(not_found)?;
+    let shell = shell_from_path_with(&path, Source::Environment, query, run)?;
+    let mut shell = shell.ok_or_else(not_found)?;
+    shell.warnings.push(Warning::EnvironmentShell);
+    Ok(shell)
+}
+
+fn env_override_with(query: VersionQuery, run: impl Run, sys: Sys) -> io::Result<Shell> {
+    let value = (sys.var)(SHELL_VAR).filter(|value| !value.is_empty());
+    let value = value.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "shell not found in $SHELLVER_SHELL",
+        )
+    })?;
+    let unsupported = || {
+        let msg = format!("unsupported shell in $SHELLVER_SHELL: {value}");
+        io::Error::new(io::ErrorKind::InvalidInput, msg)
+    };
+    let base = Path::new(&value).file_name().and_then(|name| name.to_str());
+    let name = base.and_then(shell_from_name).ok_or_else(unsupported)?;
+    // `shellver env` exports `SHELLVER_VERSION` next to `SHELLVER_NAME`, so a
+    // version exported for another shell is stale rather than an override.
+    let exported = (sys.var)(NAME_VAR).filter(|exported| !exported.is_empty());
+    let version = (sys.var)(VERSION_VAR)
+        .filter(|version| !version.is_empty())
+        .filter(|_| exported.is_none_or(|exported| exported == name));
+    // A bare name is looked up on `PATH` for its version.
+    let path = if value.contains('/') {
+        Some(value.clone())
+    } else {
+        find_in_path_with(name, sys).map(|path| path.to_string_lossy().into_owned())
+    };
+    // A given version spares asking the binary.
+    let query = if version.is_some() {
+        VersionQuery::Skip
+    } else {
+        query
+    };
+    let mut shell = match path {
+        Some(path) => shell_from_path_with(&path, Source::Override, query, run)?,
+        None => Some(Shell::new(name, Source::Override)),
+    }
+    .ok_or_else(unsupported)?;
+    if version.is_some() {
+        shell.version = version;
+    }
+    Ok(shell)
+}
+
+// Resolves a shell binary path such as `/usr/bin/zsh` by its basename and
+// queries that exact binary for its version. Unknown shells yield `None`.
+fn shell_from_path_with(
+    path: &str,
+    source: Source,
+    query: VersionQuery,
+    run: impl Run,
+) -> io::Result<Option<Shell>> {
+    let base = Path::new(path).file_name().and_then(|name| name.to_str());
+    let Some(name) = base.and_then(shell_from_name) else {
+        return Ok(None);
+    };
+    let mut shell = Shell::new(name, source);
+    shell.path = Some(PathBuf::from(path));
+    shell.restricted = is_restricted_name(Path::new(path));
+    let output = shell_version_at(name, path, query, run)?;
+    shell.version = output.version;
+    shell.version_raw = output.raw;
+    Ok(Some(shell))
+}
+
+fn login_shell_with(
+    entry: &passwd::Entry<'_>,
+    query: VersionQuery,
+    run: impl Run,
+) -> io::Result<Shell> {
+    let mut shell = shell_from_path_with(entry.shell, Source::Passwd, query, run)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "login shell not supported"))?;
+    shell.uid = Some(entry.uid);
+    Ok(shell)
+}
+
+fn entry_not_found() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "passwd entry not found")
+}
+
+// Looks `key`, a user name or uid, up with `getent passwd` first, so NSS
+// sources such as LDAP or systemd-homed are honored, and falls back to
+// parsing the passwd file where `getent` is missing or knows no such user.
+fn passwd_lookup_with<T>(
+    key: &str,
+    passwd: &str,
+    sys: Sys,
+    find: impl Fn(&str) -> Option<T>,
+) -> io::Result<T> {
+    let getent = (sys.run)(GETENT, &["passwd", key]).ok();
+    let getent = getent.and_then(|out| String::from_utf8(out).ok());
+    if let Some(found) = getent.as_deref().and_then(&find) {
+        return Ok(found);
+    }
+    find(&sys.read(passwd)?).ok_or_else(entry_not_found)
+}
+
+fn version_of_with(
+    program: &str,
+    query: VersionQuery,
+    run: impl Run,
+) -> io::Result<Option<String>> {
+    let name = program_name(Path::new(program)).unwrap_or(program);
+    let text = version_output_with(name, program, query, run)?;
+    Ok(text.and_then(|text| parse::version(name, text.as_bytes())))
+}
+
+/// Walks the parent processes of the current one and returns the first value
+/// of `var` found in their environment, see [`Detector::find_env_in_ancestry`].
+///
+/// # Errors
+///
+/// Returns an error if the parent process chain cannot be read.
+pub fn find_env_in_ancestry(var: &str) -> io::Result<Option<String>> {
+    Detector::new().find_env_in_ancestry(var)
+}
+
+fn ppid_from_path_with(path: &str, sys: Sys) -> io::Result<u32> {
+    let text = sys.read(path)?;
+    parse::status_ppid(text.as_bytes())
+}
+
+// A multi-call binary such as busybox runs the applet named by `argv[0]`, or
+// by `argv[1]` when called under its own name, e.g. `busybox sh`. Its
+// arguments are only read then.
+fn shell_from_comm(comm: &str, args: impl FnOnce() -> Vec<String>) -> Option<&'static str> {
+    if !MULTICALL.contains(&comm.trim_end()) {
+        return shell_from_name(comm);
+    }
+    let args = args();
+    let mut names = args
+        .iter()
+        .map(|arg| program_name(Path::new(arg.trim_start_matches('-'))));
+    match names.next()?? {
+        name if MULTICALL.contains(&name) => names.next()?.and_then(shell_from_name),
+        name => shell_from_name(name),
+    }
+}
+
+// A shell written in Python run by the interpreter, e.g. `python3
+// /usr/bin/xonsh` from a shebang or `python -m xonsh`. Returns the shell and
+// its script, if it was run from a file.
+fn python_shell(args: &[String]) -> Option<(&'static str, Option<&str>)> {
+    let (argv0, args) = args.split_first()?;
+    if !program_name(Path::new(argv0)).is_some_and(is_python) {
+        return None;
+    }
+    let mut args = args
+        .iter()
+        .map(String::as_str)
+        .skip_while(|arg| arg.starts_with('-') && *arg != "-m");
+    let (name, script) = match args.next()? {
+        "-m" => (args.next()?, None),
+        script => (program_name(Path::new(script))?, Some(script)),
+    };
+    let shell = PYTHON_SHELLS.iter().copied().find(|shell| name == *shell)?;
+    Some((shell, script))
+}
+
+// `python`, `python3` or a versioned `python3.12`.
+fn is_python(name: &str) -> bool {
+    name.strip_prefix("python")
+        .is_some_and(|version| version.chars().all(|ch| ch.is_ascii_digit() || ch == '.'))
+}
+
+// Restricted variants map to the shell they restrict, see `Shell::is_restricted`.
+fn shell_from_name(text: &str) -> Option<&'static str> {
+    let text = text.trim_end();
+    let text = if RESTRICTED.contains(&text) {
+        &text[1..]
+    } else {
+        text
+    };
+    SHELLS.iter().copied().find(|sh| text == *sh)
+}
+
+fn is_restricted_name(path: &Path) -> bool {
+    program_name(path).is_some_and(|name| RESTRICTED.contains(&name))
+}
+
+fn is_hidden(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::PermissionDenied | io::ErrorKind::NotFound
+    )
+}
+
+fn ps_field_with(pid: u32, field: &str, run: RunFn) -> io::Result<String> {
+    let pid = pid.to_string();
+    let out = run(PS, &["-o", field, "-p", &pid])?;
+    let text = String::from_utf8(out)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "non utf8 bytes"))?;
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "ps found no process",
+        ));
+    }
+    Ok(text.to_string())
+}
+
+fn ppid_from_ps_with(pid: u32, run: RunFn) -> io::Result<u32> {
+    ps_field_with(pid, "ppid=", run)?
+        .parse::<u32>()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "PPid parse failed"))
+}
+
+fn comm_from_ps_with(pid: u32, run: RunFn) -> io::Result<String> {
+    ps_field_with(pid, "comm=", run)
+}
+
+/// The output of a version command and the version parsed from it.
+#[derive(Debug, Clone, Default)]
+struct VersionOutput {
+    raw: Option<String>,
+    version: Option<String>,
+}
+
+fn shell_version_with(name: &str, query: VersionQuery, run: impl Run) -> io::Result<VersionOutput> {
+    shell_version_at(name, name, query, run)
+}
+
+fn shell_version_at(
+    name: &str,
+    program: &str,
+    query: VersionQuery,
+    run: impl Run,
+) -> io::Result<VersionOutput> {
+    let raw = version_output_with(name, program, query, run)?;
+    let version = raw
+        .as_deref()
+        .and_then(|raw| parse::version(name, raw.as_bytes()));
+    Ok(VersionOutput { raw, version })
+}
+
+fn package_version_with(name: &str, run: impl Run) -> Option<String> {
+    PACKAGE_QUERIES.iter().find_map(|(program, args)| {
+        let args: Vec<&str> = args.iter().copied().chain([name]).collect();
+        let out = run(program, &args).ok()?;
+        parse::version(name, &out)
+    })
+}
+
+// Reads the version the nearest shell left in our environment, e.g.
+// `FISH_VERSION`, without spawning anything. A variable of another shell, or
+// one that does not parse as a version of `name`, is ignored.
+fn inherited_version_with(name: &str, var: VarFn) -> Option<String> {
+    let (_, key) = VERSION_VARS.iter().find(|(shell, _)| *shell == name)?;
+    parse::version(name, var(key)?.as_bytes())
+}
+
+/// The binary queried for a process' version, see `Detector::process_shell_with`.
+struct Program {
+    name: &'static str,
+    path: String,
+    on_path: bool,
+}
+
+/// The versions found during one call, per binary, backed by the detector's
+/// [`CacheStore`] if it has one.
+struct Versions<'a> {
+    query: VersionQuery,
+    seen: HashMap<String, VersionOutput>,
+    store: Option<(&'a dyn CacheStore, Duration)>,
+}
+
+impl Versions<'_> {
+    // Unknown versions are not stored, the binary may be fixed or replaced.
+    // Stores keep only the version, not the raw output.
+    fn get_or_query(
+        &mut self,
+        path: &str,
+        query: impl FnOnce() -> io::Result<VersionOutput>,
+    ) -> io::Result<VersionOutput> {
+        if let Some(output) = self.seen.get(path) {
+            return Ok(output.clone());
+        }
+        let key = format!("version:{path}");
+        let stored = self
+            .store
+            .and_then(|(store, _)| store.get(&key).ok().flatten());
+        let output = if stored.is_some() {
+            VersionOutput {
+                raw: None,
+                version: stored,
+            }
+        } else {
+            let output = query()?;
+            if let (Some((store, ttl)), Some(version)) = (self.store, &output.version) {
+                _ = store.put(&key, version, ttl);
+            }
+            output
+        };
+        self.seen.insert(path.to_string(), output.clone());
+        Ok(output)
+    }
+}
+
+// Versions are cached per binary, so nested shells of the same kind only
+// spawn one version command.
+fn query_version_with(
+    shell: &mut Shell,
+    program: &Program,
+    run: impl Run,
+    versions: &mut Versions,
+) -> io::Result<()> {
+    let query = versions.query;
+    if query == VersionQuery::Skip {
+        return Ok(());
+    }
+    let output = versions.get_or_query(&program.path, || {
+        if program.on_path {
+            shell_version_with(program.name, query, run)
+        } else {
+            shell_version_at(program.name, &program.path, query, run)
+        }
+    })?;
+    if output.version.is_some() && program.on_path {
+        shell.warnings.push(Warning::VersionFromPath);
+    }
+    shell.version = output.version;
+    shell.version_raw = output.raw;
+    Ok(())
+}
+
+// Splits shell arguments into the leading options and the first operand, a
+// script or the command of `-c`. `-o` and `-O` take an argument, like
+// `bash -o posix`.
+fn split_options(flags: &[String]) -> (Vec<&str>, Option<&str>) {
+    let mut options = Vec::new();
+    let mut flags = flags.iter().map(String::as_str);
+    while let Some(flag) = flags.next() {
+        if flag == "--" {
+            return (options, flags.next());
+        }
+        if flag.len() < 2 || !flag.starts_with(['-', '+']) {
+            return (options, Some(flag));
+        }
+        options.push(flag);
+        if matches!(&flag[1..], "o" | "O") {
+            flags.next();
+        }
+    }
+    (options, None)
+}
+
+// Maps an executable name to a shell implementation, including multi-call
+// binaries that provide `sh`.
+fn implementation_from_name(name: &str) -> Option<&'static str> {
+    let multicall = || {
+        MULTICALL
+            .iter()
+            .copied()
+            .find(|multicall| name == *multicall)
+    };
+    shell_from_name(name).or_else(multicall)
+}
+
+fn has_posix_flag(args: &[String]) -> bool {
+    let Some((_, flags)) = args.split_first() else {
+        return false;
+    };
+    let (options, _) = split_options(flags);
+    let posix = flags
+        .windows(2)
+        .any(|pair| pair[0] == "-o" && pair[1] == "posix");
+    posix || options.contains(&"--posix")
+}
+
+// Bash, ksh and zsh all take `-r`, also in a cluster such as `-ir`.
+fn has_restricted_flag(args: &[String]) -> bool {
+    let Some((_, flags)) = args.split_first() else {
+        return false;
+    };
+    let (options, _) = split_options(flags);
+    let restricted = flags
+        .windows(2)
+        .any(|pair| pair[0] == "-o" && pair[1] == "restricted");
+    restricted
+        || options.iter().any(|option| {
+            *option == "--restricted"
+                || option
+                    .strip_prefix('-')
+                    .is_some_and(|cluster| !cluster.starts_with('-') && cluster.contains('r'))
+        })
+}
+
+fn is_tty(path: &Path) -> bool {
+    path.starts_with("/dev/pts")
+        || path
+            .to_str()
+            .is_some_and(|path| path.starts_with("/dev/tty"))
+}
+
+// The kernel marks executables replaced since the process started, e.g. by a
+// package upgrade, and removed directories with a ` (deleted)` suffix.
+fn strip_deleted(exe: &Path) -> (PathBuf, bool) {
+    let stripped = exe
+        .to_str()
+        .and_then(|path| path.strip_suffix(" (deleted)"));
+    stripped.map_or_else(
+        || (exe.to_path_buf(), false),
+        |path| (PathBuf::from(path), true),
+    )
+}
+
+fn file_name(path: &Path) -> Option<&str> {
+    path.file_name().and_then(|name| name.to_str())
+}
+
+// `program` is the command to run, either the shell name looked up on `PATH`
+// or a full path such as the one in `$SHELL`.
+fn version_output_with(
+    name: &str,
+    program: &str,
+    query: VersionQuery,
+    run: impl Run,
+) -> io::Result<Option<String>> {
+    let builtin = match query {
+        VersionQuery::Builtin => builtin_args(name),
+        VersionQuery::Banner => None,
+        VersionQuery::Skip => return Ok(None),
+    };
+    let builtin = builtin
+        .and_then(|args| run(program, args).ok())
+        .and_then(|out| String::from_utf8(out).ok())
+        .filter(|text| !text.trim().is_empty());
+    if builtin.is_some() {
+        return Ok(builtin);
+    }
+    let Some(args) = shell_args(name) else {
+        return Ok(None);
+    };
+    let text = run(program, args).and_then(|out| {
+        String::from_utf8(out)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "non utf8 bytes"))
+    });
+    match text {
+        Err(_) if UNVERSIONED.contains(&name) => Ok(None),
+        Err(err) if err.kind() == io::ErrorKind::TimedOut => Ok(None),
+        text => text.map(Some),
+    }
+}
+
+fn installed_with(
+    shells_files: &[PathBuf],
+    query: VersionQuery,
+    run: impl Run,
+    sys: Sys,
+) -> Vec<Shell> {
+    let listed = listed_shells_with(shells_files, sys);
+    let on_path = SHELLS
+        .iter()
+        .filter_map(|name| find_in_path_with(name, sys));
+    let candidates: Vec<PathBuf> = listed.into_iter().chain(on_path).collect();
+
+    let mut seen = HashSet::new();
+    let mut shells = Vec::new();
+    for path in candidates {
+        let Some(name) = shell_from_file(&path) else {
+            continue;
+        };
+        if !(sys.is_exec)(&path) {
+            continue;
+        }
+        if !seen.insert((sys.canonicalize)(&path).unwrap_or_else(|_| path.clone())) {
+            continue;
+        }
+        shells.push(installed_shell_with(name, path, query, run));
+    }
+    shells.sort_by(|a, b| {
+        let by_version = || version_cmp(a.version.as_deref(), b.version.as_deref());
+        a.name.cmp(&b.name).then_with(by_version)
+    });
+    shells
+}
+
+// Reads the shells databases with `getusershell` semantics: the first word of
+// each line counts, comments are skipped, and without any readable database
+// only `/bin/sh` and `/bin/csh` count as listed.
+fn listed_shells_with(shells_files: &[PathBuf], sys: Sys) -> Vec<PathBuf> {
+    let texts: Vec<String> = shells_files
+        .iter()
+        .filter_map(|file| sys.read(&file.to_string_lossy()).ok())
+        .collect();
+    if texts.is_empty() {
+        return DEFAULT_SHELLS.map(PathBuf::from).to_vec();
+    }
+    texts
+        .iter()
+        .flat_map(|text| text.lines())
+        .filter_map(|line| line.split('#').next()?.split_whitespace().next())
+        .map(PathBuf::from)
+        .collect()
+}
+
+fn installed_shell_with(
+    name: &'static str,
+    path: PathBuf,
+    query: VersionQuery,
+    run: impl Run,
+) -> Shell {
+    let mut shell = Shell::new(name, Source::Installed);
+    let output = path
+        .to_str()
+        .and_then(|path| shell_version_at(name, path, query, run).ok())
+        .unwrap_or_default();
+    shell.version = output.version;
+    shell.version_raw = output.raw;
+    shell.restricted = is_restricted_name(&path);
+    shell.path = Some(path);
+    shell
+}
+
+fn installations_with(
+    name: &str,
+    query: VersionQuery,
+    run: impl Run,
+    sys: Sys,
+) -> io::Result<Vec<Shell>> {
+    let unknown = || io::Error::new(io::ErrorKind::InvalidInput, "unknown shell");
+    let name = shell_from_name(name).ok_or_else(unknown)?;
+    let mut dirs: Vec<PathBuf> = (sys.var)("PATH")
+        .map(|paths| env::split_paths(&paths).collect())
+        .unwrap_or_default();
+    dirs.extend(PREFIXES.iter().map(PathBuf::from));
+    if let Some(home) = (sys.var)("HOME") {
+        dirs.push(Path::new(&home).join(NIX_PROFILE));
+    }
+
+    let names = executable_names(name, sys);
+    let paths = dirs
+        .iter()
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)));
+
+    let mut seen = HashSet::new();
+    let mut shells = Vec::new();
+    for path in paths {
+        if !(sys.is_exec)(&path) {
+            continue;
+        }
+        if !seen.insert((sys.canonicalize)(&path).unwrap_or_else(|_| path.clone())) {
+            continue;
+        }
+        shells.push(installed_shell_with(name, path, query, run));
+    }
+    Ok(shells)
+}
+
+// Orders versions by their numeric components, so `5.10` sorts after `5.9`,
+// with unknown versions first.
+fn version_cmp(a: Option<&str>, b: Option<&str>) -> Ordering {
+    let parts = |version: &str| -> Vec<u64> {
+        version
+            .split(|ch: char| !ch.is_ascii_digit())
+            .filter_map(|part| part.parse().ok())
+            .collect()
+    };
+    match (a, b) {
+        (Some(a), Some(b)) => parts(a).cmp(&parts(b)).then_with(|| a.cmp(b)),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+// 64-bit FNV-1a, which unlike `DefaultHasher` is fixed across releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// Reads up to three leading numeric components, skipping a prefix such as
+// mksh's `R` and stopping at the first suffix.
+fn semver_parts(version: &str) -> Option<[u64; 3]> {
+    let version = version.trim_start_matches(|ch: char| !ch.is_ascii_digit());
+    let mut parts = [0; 3];
+    let mut found = 0;
+    for (slot, part) in parts.iter_mut().zip(version.split('.')) {
+        let digits = part
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(part.len());
+        let Ok(number) = part[..digits].parse() else {
+            break;
+        };
+        *slot = number;
+        found += 1;
+        if digits < part.len() {
+            break;
+        }
+    }
+    (found > 0).then_some(parts)
+}
+
+// Turns a requirement such as `>=3.2, <6` into half-open version ranges that
+// must all hold, each with an inclusive lower and exclusive upper bound.
+type Range = (Option<[u64; 3]>, Option<[u64; 3]>);
+
+fn requirement_ranges(requirement: &str) -> io::Result<Vec<Range>> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid version requirement: {requirement:?}"),
+        )
+    };
+    let mut ranges = Vec::new();
+    for comparator in requirement.split(',').map(str::trim) {
+        if comparator == "*" {
+            ranges.push((None, None));
+            continue;
+        }
+        let split = comparator
+            .find(|ch: char| ch.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let (op, version) = comparator.split_at(split);
+        let numbers: Vec<&str> = version.split('.').collect();
+        if numbers.len() > 3
+            || numbers
+                .iter()
+                .any(|n| n.is_empty() || !n.bytes().all(|b| b.is_ascii_digit()))
+        {
+            return Err(invalid());
+        }
+        let mut low = [0_u64; 3];
+        for (slot, number) in low.iter_mut().zip(&numbers) {
+            *slot = number.parse().map_err(|_| invalid())?;
+        }
+        // The version just past everything matching the given components,
+        // e.g. `5.3.0` for `5.2`.
+        let bump = |index: usize| {
+            let mut high = low;
+            high[index] = high[index].checked_add(1).ok_or_else(invalid)?;
+            high[index + 1..].fill(0);
+            Ok::<_, io::Error>(high)
+        };
+        let last = numbers.len() - 1;
+        let range = match op.trim() {
+            "" | "^" => {
+                let first = low[..last].iter().position(|&n| n != 0).unwrap_or(last);
+                (Some(low), Some(bump(first)?))
+            }
+            "~" => (Some(low), Some(bump(last.min(1))?)),
+            "=" => (Some(low), Some(bump(last)?)),
+            ">=" => (Some(low), None),
+            ">" => (Some(bump(last)?), None),
+            "<" => (None, Some(low)),
+            "<=" => (None, Some(bump(last)?)),
+            _ => return Err(invalid()),
+        };
+        ranges.push(range);
+    }
+    Ok(ranges)
+}
+
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    find_in_path_with(name, LIVE)
+}
+
+fn find_in_path_with(name: &str, sys: Sys) -> Option<PathBuf> {
+    let paths = (sys.var)("PATH")?;
+    let names = executable_names(name, sys);
+    env::split_paths(&paths)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| (sys.is_exec)(path))
+}
+
+// The file names `name` is looked up by. Windows finds `pwsh` as `pwsh.exe`
+// and tries every extension in `PATHEXT` in order.
+fn executable_names(name: &str, sys: Sys) -> Vec<String> {
+    let pathext = cfg!(windows).then(|| (sys.var)("PATHEXT")).flatten();
+    with_extensions(name, pathext.as_deref())
+}
+
+fn with_extensions(name: &str, pathext: Option<&str>) -> Vec<String> {
+    let Some(pathext) = pathext else {
+        return vec![name.to_string()];
+    };
+    pathext
+        .split(';')
+        .filter(|ext| ext.starts_with('.') && ext.len() > 1)
+        .map(|ext| format!("{name}{}", ext.to_ascii_lowercase()))
+        .collect()
+}
+
+fn shell_from_file(path: &Path) -> Option<&'static str> {
+    program_name(path).and_then(shell_from_name)
+}
+
+// The file name of a program without an `.exe` extension, so `pwsh.exe` is
+// queried like `pwsh`.
+fn program_name(path: &Path) -> Option<&str> {
+    let is_exe = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"));
+    let base = if is_exe {
+        path.file_stem()
+    } else {
+        path.file_name()
+    };
+    base.and_then(|name| name.to_str())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+// App Execution Aliases such as `WindowsApps\pwsh.exe` are reparse points
+// that cannot be followed, so only the link itself is checked.
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|meta| !meta.is_dir())
+}
+
+fn builtin_args(name: &str) -> Option<&'static [&'static str]> {
+    BUILTIN_COMMANDS
+        .iter()
+        .find(|(shell, _)| *shell == name)
+        .map(|(_, args)| *args)
+}
+
+fn shell_args(name: &str) -> Option<&'static [&'static str]> {
+    VERSION_COMMANDS
+        .iter()
+        .find(|(shell, _)| *shell == name)
+        .map_or(Some(ARGS_VERSION), |(_, args)| *args)
+}
+
+#[cfg(test)]
+mod cache_tests;
+#[cfg(test)]
+mod capability_tests;
+#[cfg(test)]
+mod lib_tests;
+#[cfg(test)]
+mod parse_tests;
+#[cfg(test)]
+mod passwd_tests;
+#[cfg(test)]
+mod render_tests;
+#[cfg(test)]
+mod snapshot_tests;
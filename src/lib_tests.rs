@@ -5,41 +5,105 @@ mod tests {
     #[test]
     fn supported_shells_size() {
         let shells = Shell::supported_shells();
-        assert_eq!(shells.len(), 13);
+        assert_eq!(shells.len(), 21);
         assert!(["bash", "zsh", "fish"].iter().all(|s| shells.contains(s)));
     }
 
+    #[test]
+    fn family_of_supported_shells() {
+        let family = Family::of;
+        assert_eq!(family("pwsh"), Family::PowerShell);
+        assert_eq!(family("busybox"), Family::Bourne);
+        assert_eq!(family("tcsh"), Family::Csh);
+        assert_eq!(family("nu"), Family::Other);
+        assert_eq!(family("osh"), Family::Bourne);
+        assert_eq!(family("yash"), Family::Bourne);
+        assert_eq!(family("murex"), Family::Other);
+        assert_eq!(family("ysh"), Family::Other);
+        assert_eq!(
+            Shell::new("pwsh", Source::Process).family().as_str(),
+            "powershell"
+        );
+    }
+
+    fn sys(read: ReadFn, run: RunFn) -> Sys {
+        Sys {
+            read,
+            run,
+            run_timeout: |_, _, _| unreachable!("probing disabled"),
+            var: |_| None,
+            list: |_| unreachable!("no listing"),
+            read_bytes: |_| Err(io::Error::new(io::ErrorKind::PermissionDenied, "deny")),
+            read_link: |_| Err(io::Error::new(io::ErrorKind::PermissionDenied, "deny")),
+            is_exec: |_| false,
+            canonicalize: |path| Ok(path.to_path_buf()),
+            trace: false,
+        }
+    }
+
     fn read_mock(text: &str) -> io::Result<String> {
         Ok(text.to_string())
     }
 
+    fn no_args() -> Vec<String> {
+        unreachable!("only read for multi-call binaries")
+    }
+
     #[test]
-    fn shell_from_pid_returns_some() {
-        let val = shell_from_pid_with("bash\n", read_mock).unwrap();
+    fn shell_from_comm_returns_some() {
+        let val = shell_from_comm("bash\n", no_args);
         assert_eq!(val, Some("bash"));
     }
 
     #[test]
-    fn shell_from_pid_returns_none() {
-        let val = shell_from_pid_with("unknown\n", read_mock).unwrap();
+    fn shell_from_comm_returns_none() {
+        let val = shell_from_comm("unknown\n", no_args);
         assert_eq!(val, None);
     }
 
+    #[test]
+    fn shell_from_comm_multicall_applets() {
+        let args = |args: &[&str]| args.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let cases: [(&str, &[&str], Option<&str>); 5] = [
+            ("busybox\n", &["busybox", "sh", "-c", "true"], Some("sh")),
+            ("busybox\n", &["/bin/busybox", "ash"], Some("ash")),
+            ("busybox\n", &["-ash"], Some("ash")),
+            ("toybox\n", &["toybox", "sh"], Some("sh")),
+            ("busybox\n", &["busybox", "httpd"], None),
+        ];
+        for (comm, argv, shell) in cases {
+            assert_eq!(shell_from_comm(comm, || args(argv)), shell, "{argv:?}");
+        }
+        assert_eq!(shell_from_comm("busybox\n", Vec::new), None);
+    }
+
     #[test]
     fn ppid_from_path_parse_ok() {
-        let val = ppid_from_path_with("Name:\tbash\nPPid:\t123\n", read_mock).unwrap();
+        let val = ppid_from_path_with(
+            "Name:\tbash\nPPid:\t123\n",
+            sys(read_mock, |_, _| unreachable!("no subprocess")),
+        )
+        .unwrap();
         assert_eq!(val, 123);
     }
 
     #[test]
     fn ppid_from_path_missing() {
-        let err = ppid_from_path_with("Name:\tbash\n", read_mock).unwrap_err();
+        let err = ppid_from_path_with(
+            "Name:\tbash\n",
+            sys(read_mock, |_, _| unreachable!("no subprocess")),
+        )
+        .unwrap_err();
         assert_eq!(err.kind(), io::ErrorKind::NotFound);
     }
 
     #[test]
     fn ppid_from_path_parse_error() {
-        let err = ppid_from_path_with("Name:\tbash\nPPid:\tbad\n", read_mock).unwrap_err();
+        let err = ppid_from_path_with(
+            "Name:\tbash\nPPid:\tbad\n",
+            sys(read_mock, |_, _| unreachable!("no subprocess")),
+        )
+        .unwrap_err();
         assert_eq!(err.kind(), io::ErrorKind::InvalidData);
     }
 
@@ -49,7 +113,11 @@ mod tests {
             Err(io::Error::new(io::ErrorKind::PermissionDenied, "deny"))
         }
 
-        let err = ppid_from_path_with("/proc/1/status", read_mock_err).unwrap_err();
+        let err = ppid_from_path_with(
+            "/proc/1/status",
+            sys(read_mock_err, |_, _| unreachable!("no subprocess")),
+        )
+        .unwrap_err();
         assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
     }
 
@@ -65,19 +133,21 @@ mod tests {
 
     #[test]
     fn shell_version_on_invalid_command() {
-        let err = shell_version_with("", run_mock).unwrap_err();
+        let err = shell_version_with("", VersionQuery::Banner, run_mock).unwrap_err();
         assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
     }
 
     #[test]
     fn shell_version_on_invalid_input() {
-        let err = shell_version_with("bad_utf", run_mock).unwrap_err();
+        let err = shell_version_with("bad_utf", VersionQuery::Banner, run_mock).unwrap_err();
         assert_eq!(err.kind(), io::ErrorKind::InvalidData);
     }
 
     #[test]
     fn shell_version_returns_none() {
-        let val = shell_version_with("no version here", run_mock).unwrap();
+        let val = shell_version_with("no version here", VersionQuery::Banner, run_mock)
+            .unwrap()
+            .version;
         assert_eq!(val, None);
     }
 
@@ -87,25 +157,41 @@ mod tests {
             unreachable!("should not be reachable");
         }
 
-        let val = shell_version_with("dash", run_never).unwrap();
+        let val = shell_version_with("dash", VersionQuery::Banner, run_never)
+            .unwrap()
+            .version;
         assert_eq!(val, None);
     }
 
     #[test]
     fn shell_version_returns_some_bash() {
-        let val = shell_version_with("GNU bash, version 5.3.9(1)-release", run_mock).unwrap();
+        let val = shell_version_with(
+            "GNU bash, version 5.3.9(1)-release",
+            VersionQuery::Banner,
+            run_mock,
+        )
+        .unwrap()
+        .version;
         assert_eq!(val, Some("5.3.9".to_string()));
     }
 
     #[test]
     fn shell_version_returns_some_ksh() {
-        let val = shell_version_with("sh (AT&T Research) 2020.0.0", run_mock).unwrap();
+        let val = shell_version_with(
+            "sh (AT&T Research) 2020.0.0",
+            VersionQuery::Banner,
+            run_mock,
+        )
+        .unwrap()
+        .version;
         assert_eq!(val, Some("2020.0.0".to_string()));
     }
 
     #[test]
     fn shell_version_returns_some_elvish() {
-        let val = shell_version_with("0.21.0+archlinux1", run_mock).unwrap();
+        let val = shell_version_with("0.21.0+archlinux1", VersionQuery::Banner, run_mock)
+            .unwrap()
+            .version;
         assert_eq!(val, Some("0.21.0".to_string()));
     }
 
@@ -115,7 +201,9 @@ mod tests {
             Ok(b"@(#)MIRBSD KSH R59 2020/10/31".to_vec())
         }
 
-        let val = shell_version_with("mksh", run_mksh).unwrap();
+        let val = shell_version_with("mksh", VersionQuery::Banner, run_mksh)
+            .unwrap()
+            .version;
         assert_eq!(val, Some("R59".to_string()));
     }
 
@@ -123,6 +211,7 @@ mod tests {
         match path {
             "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
             "/proc/100/comm" => Ok("bash\n".to_string()),
+            "/proc/100/status" => Ok("PPid:\t1\n".to_string()),
             _ => unreachable!("bad path"),
         }
     }
@@ -131,19 +220,48 @@ mod tests {
         Ok(b"bash 5.2.0".to_vec())
     }
 
+    fn run_detect_no_ps(name: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+        if name == PS {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "no ps"));
+        }
+        run_detect_ok(name, args)
+    }
+
     #[test]
     fn detect_with_ok() {
         fn read_detect_ok(path: &str) -> io::Result<String> {
             match path {
                 "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
                 "/proc/100/comm" => Ok("bash\n".to_string()),
+                "/proc/100/status" => Ok("PPid:\t1\n".to_string()),
                 _ => unreachable!("bad path"),
             }
         }
 
-        let shell = Shell::detect_with(read_detect_ok, run_detect_ok).unwrap();
+        let shell = Detector::new()
+            .detect_with(sys(read_detect_ok, run_detect_ok))
+            .unwrap();
         assert_eq!(shell.name(), "bash");
         assert_eq!(shell.version(), Some("5.2.0".to_string()));
+        assert_eq!(shell.version_raw(), Some("bash 5.2.0"));
+        assert_eq!(shell.version_extra(), None);
+        assert_eq!(shell.pid(), Some(100));
+        assert_eq!(shell.ppid(), Some(1));
+    }
+
+    #[test]
+    fn detect_with_cached_version() {
+        let store: Arc<dyn CacheStore> = Arc::new(cache::MemoryStore::default());
+        let detector = Detector::new().cache(Arc::clone(&store), Duration::from_secs(60));
+        let first = detector.detect_with(sys(read_detect_run_err, run_detect_ok));
+        assert_eq!(first.unwrap().version().as_deref(), Some("5.2.0"));
+        assert_eq!(store.get("version:bash").unwrap().as_deref(), Some("5.2.0"));
+        let again = Detector::new().cache(store, Duration::from_secs(60));
+        let cached = again.detect_with(sys(read_detect_run_err, |_, _| unreachable!("cached")));
+        let cached = cached.unwrap();
+        assert_eq!(cached.version().as_deref(), Some("5.2.0"));
+        assert_eq!(cached.version_raw(), None);
+        assert_eq!(cached.version_extra(), None);
     }
 
     #[test]
@@ -157,7 +275,9 @@ mod tests {
             }
         }
 
-        let err = Shell::detect_with(read_detect_not_found, run_detect_ok).unwrap_err();
+        let err = Detector::new()
+            .detect_with(sys(read_detect_not_found, run_detect_ok))
+            .unwrap_err();
         assert_eq!(err.kind(), io::ErrorKind::NotFound);
     }
 
@@ -170,7 +290,9 @@ mod tests {
             }
         }
 
-        let err = Shell::detect_with(read_detect_err, run_detect_ok).unwrap_err();
+        let err = Detector::new()
+            .detect_with(sys(read_detect_err, run_detect_ok))
+            .unwrap_err();
         assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
     }
 
@@ -179,12 +301,16 @@ mod tests {
         fn read_detect_err(path: &str) -> io::Result<String> {
             match path {
                 "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
-                "/proc/100/comm" => Err(io::Error::new(io::ErrorKind::PermissionDenied, "deny")),
+                "/proc/100/comm" | "/proc/100/stat" => {
+                    Err(io::Error::new(io::ErrorKind::PermissionDenied, "deny"))
+                }
                 _ => unreachable!("bad path"),
             }
         }
 
-        let err = Shell::detect_with(read_detect_err, run_detect_ok).unwrap_err();
+        let err = Detector::new()
+            .detect_with(sys(read_detect_err, run_detect_no_ps))
+            .unwrap_err();
         assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
     }
 
@@ -194,22 +320,1972 @@ mod tests {
             match path {
                 "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
                 "/proc/100/comm" => Ok("unknown\n".to_string()),
-                "/proc/100/status" => Err(io::Error::new(io::ErrorKind::PermissionDenied, "deny")),
+                "/proc/100/status" | "/proc/100/stat" => {
+                    Err(io::Error::new(io::ErrorKind::PermissionDenied, "deny"))
+                }
                 _ => unreachable!("bad path"),
             }
         }
 
-        let err = Shell::detect_with(read_detect_err, run_detect_ok).unwrap_err();
+        let err = Detector::new()
+            .detect_with(sys(read_detect_err, run_detect_no_ps))
+            .unwrap_err();
         assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
     }
 
+    #[test]
+    fn detect_with_hidepid_stat_fallback() {
+        fn read_detect_stat(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/proc/100/comm" | "/proc/100/status" | "/proc/200/comm" | "/proc/200/status" => {
+                    Err(io::Error::new(io::ErrorKind::PermissionDenied, "deny"))
+                }
+                "/proc/100/stat" => Ok("100 (sudo rs) S 200 100 100 0".to_string()),
+                "/proc/200/stat" => Ok("200 (bash) S 1 200 200 0".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        let shell = Detector::new()
+            .detect_with(sys(read_detect_stat, run_detect_no_ps))
+            .unwrap();
+        assert_eq!(shell.name(), "bash");
+    }
+
+    #[test]
+    fn detect_with_hidepid_ps_fallback() {
+        fn read_detect_hidden(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                _ => Err(io::Error::new(io::ErrorKind::NotFound, "hidden")),
+            }
+        }
+
+        fn run_detect_ps(name: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+            match (name, args) {
+                (PS, ["-o", "comm=", "-p", "100"]) => Ok(b"zsh\n".to_vec()),
+                (PS, ["-o", "ppid=", "-p", "100"]) => Ok(b"1\n".to_vec()),
+                (PS, _) => unreachable!("bad ps args"),
+                _ => Ok(b"zsh 5.9".to_vec()),
+            }
+        }
+
+        let shell = Detector::new()
+            .detect_with(sys(read_detect_hidden, run_detect_ps))
+            .unwrap();
+        assert_eq!(shell.name(), "zsh");
+        assert_eq!(shell.version(), Some("5.9".to_string()));
+    }
+
+    #[test]
+    fn ppid_from_ps_empty() {
+        fn run_ps_empty(_name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+            Ok(Vec::new())
+        }
+
+        let err = ppid_from_ps_with(100, run_ps_empty).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
     #[test]
     fn detect_with_run_error() {
         fn run_detect_err(_name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
             Err(io::Error::new(io::ErrorKind::InvalidInput, "bad cmd"))
         }
 
-        let err = Shell::detect_with(read_detect_run_err, run_detect_err).unwrap_err();
+        let err = Detector::new()
+            .detect_with(sys(read_detect_run_err, run_detect_err))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn log_level_parse() {
+        assert_eq!(LogLevel::parse("debug"), Some(LogLevel::Debug));
+        assert_eq!(LogLevel::parse(" INFO\n"), Some(LogLevel::Info));
+        assert_eq!(LogLevel::parse("0"), Some(LogLevel::Off));
+        assert_eq!(LogLevel::parse("trace"), None);
+        assert!(LogLevel::Debug > LogLevel::Info);
+    }
+
+    #[test]
+    fn debug_log_keeps_mocked_readers() {
+        let plain = Detector::new()
+            .detect_with(sys(read_detect_run_err, run_detect_ok))
+            .unwrap();
+        let traced = Detector::new()
+            .log_level(LogLevel::Debug)
+            .detect_with(sys(read_detect_run_err, run_detect_ok))
+            .unwrap();
+        assert_eq!(
+            (traced.pid(), traced.version()),
+            (plain.pid(), plain.version())
+        );
+    }
+
+    #[test]
+    fn detect_skip_version_query() {
+        let sys = Sys {
+            var: |key| (key == "BASH_VERSION").then(|| "5.2.15(1)-release".to_string()),
+            ..sys(read_detect_run_err, |_, _| unreachable!("no version asked"))
+        };
+        let shell = Detector::new()
+            .version_query(VersionQuery::Skip)
+            .detect_with(sys)
+            .unwrap();
+        assert_eq!(shell.name(), "bash");
+        assert_eq!(shell.version(), None);
+        assert!(shell.warnings().is_empty());
+        let skip = version_output_with("bash", "bash", VersionQuery::Skip, |_, _| {
+            unreachable!("no version asked")
+        });
+        assert_eq!(skip.unwrap(), None);
+    }
+
+    #[test]
+    fn detect_with_version_timeout() {
+        fn run_hung(_name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+            Err(io::Error::new(io::ErrorKind::TimedOut, "command timed out"))
+        }
+
+        let shell = Detector::new()
+            .detect_with(sys(read_detect_run_err, run_hung))
+            .unwrap();
+        assert_eq!(shell.name(), "bash");
+        assert_eq!(shell.version(), None);
+
+        let sys = Sys {
+            run_timeout: |name, _, timeout| {
+                assert_eq!(timeout, Duration::from_millis(300));
+                run_detect_ok(name, &[])
+            },
+            ..sys(read_detect_run_err, |_, _| unreachable!("timeout set"))
+        };
+        let shell = Detector::new()
+            .version_timeout(Duration::from_millis(300))
+            .detect_with(sys)
+            .unwrap();
+        assert_eq!(shell.version(), Some("5.2.0".to_string()));
+    }
+
+    #[test]
+    fn version_command_per_shell() {
+        assert_eq!(
+            Shell::version_command("bash").as_deref(),
+            Some("bash --version")
+        );
+        assert_eq!(
+            Shell::version_command("ksh").as_deref(),
+            Some("ksh -c 'printf %s \"$KSH_VERSION\"'")
+        );
+        assert_eq!(Shell::version_command("dash"), None);
+    }
+
+    #[test]
+    fn self_check_reports_raw_output() {
+        fn installed(name: &str) -> bool {
+            ["bash", "mksh", "dash"].contains(&name)
+        }
+
+        fn run_self_check(name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+            match name {
+                "bash" => Ok(b"GNU bash, version 5.3.9(1)-release".to_vec()),
+                "mksh" => Ok(b"unexpected banner".to_vec()),
+                _ => unreachable!("not installed"),
+            }
+        }
+
+        let checks = Shell::self_check_with(installed, run_self_check);
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[0].name(), "bash");
+        assert_eq!(checks[0].version(), Some("5.3.9"));
+        assert_eq!(checks[1].name(), "mksh");
+        assert_eq!(checks[1].version(), None);
+        assert_eq!(checks[1].raw(), "unexpected banner");
+    }
+
+    #[test]
+    fn detect_with_proc_root() {
+        fn read_detect_root(path: &str) -> io::Result<String> {
+            match path {
+                "/host/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/host/proc/100/comm" => Ok("fish\n".to_string()),
+                "/host/proc/100/status" => Ok("PPid:\t1\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        let detector = Detector::new().proc_root("/host/proc");
+        let shell = detector
+            .detect_with(sys(read_detect_root, run_detect_ok))
+            .unwrap();
+        assert_eq!(shell.name(), "fish");
+    }
+
+    fn read_walk_not_found(path: &str) -> io::Result<String> {
+        match path {
+            "/proc/self/status" => Ok("PPid:\t1\n".to_string()),
+            _ => unreachable!("bad path"),
+        }
+    }
+
+    fn run_env_shell(name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+        assert_eq!(name, "/usr/local/bin/fish");
+        Ok(b"fish, version 3.7.1".to_vec())
+    }
+
+    #[test]
+    fn detect_with_env_shell_fallback() {
+        let sys = Sys {
+            var: |key| (key == "SHELL").then(|| "/usr/local/bin/fish".to_string()),
+            ..sys(read_walk_not_found, run_env_shell)
+        };
+        let detector = Detector::new().strategies([Strategy::ProcWalk, Strategy::EnvShell]);
+        let shell = detector.detect_with(sys).unwrap();
+        assert_eq!(shell.name(), "fish");
+        assert_eq!(shell.version(), Some("3.7.1".to_string()));
+        assert_eq!(shell.source(), Source::Environment);
+        assert_eq!(shell.warnings(), [Warning::EnvironmentShell]);
+    }
+
+    #[test]
+    fn detect_with_env_shell_disabled() {
+        let sys = Sys {
+            var: |_| unreachable!("env fallback disabled"),
+            ..sys(read_walk_not_found, run_env_shell)
+        };
+        let err = Detector::new().detect_with(sys).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn detect_with_env_shell_unknown() {
+        let sys = Sys {
+            var: |_| Some("/usr/bin/python3".to_string()),
+            ..sys(read_walk_not_found, run_env_shell)
+        };
+        let detector = Detector::new().strategies([Strategy::EnvShell]);
+        let err = detector.detect_with(sys).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn detect_with_env_override() {
+        let override_first =
+            Detector::new().strategies([Strategy::EnvOverride, Strategy::ProcWalk]);
+        let by_path = Sys {
+            var: |key| match key {
+                "SHELLVER_SHELL" => Some("/usr/local/bin/fish".to_string()),
+                _ => None,
+            },
+            ..sys(read_detect_run_err, run_env_shell)
+        };
+        let shell = override_first.detect_with(by_path).unwrap();
+        assert_eq!(shell.name(), "fish");
+        assert_eq!(shell.version(), Some("3.7.1".to_string()));
+        assert_eq!(shell.source(), Source::Override);
+        let by_name = Sys {
+            var: |key| match key {
+                "SHELLVER_SHELL" => Some("zsh".to_string()),
+                "SHELLVER_VERSION" => Some("5.9".to_string()),
+                _ => None,
+            },
+            ..sys(read_detect_run_err, |_, _| unreachable!("version given"))
+        };
+        let shell = override_first.detect_with(by_name).unwrap();
+        assert_eq!(
+            (shell.name(), shell.version()),
+            ("zsh", Some("5.9".to_string()))
+        );
+        assert_eq!(shell.path(), None);
+        // A version `shellver env` exported for another shell is stale.
+        let stale = Sys {
+            var: |key| match key {
+                "SHELLVER_SHELL" => Some("/usr/local/bin/fish".to_string()),
+                "SHELLVER_NAME" => Some("bash".to_string()),
+                "SHELLVER_VERSION" => Some("5.2.15".to_string()),
+                _ => None,
+            },
+            ..sys(read_detect_run_err, run_env_shell)
+        };
+        let shell = override_first.detect_with(stale).unwrap();
+        assert_eq!(shell.version(), Some("3.7.1".to_string()));
+        let same = Sys {
+            var: |key| match key {
+                "SHELLVER_SHELL" => Some("/usr/local/bin/fish".to_string()),
+                "SHELLVER_NAME" => Some("fish".to_string()),
+                "SHELLVER_VERSION" => Some("4.0.0".to_string()),
+                _ => None,
+            },
+            ..sys(read_detect_run_err, |_, _| unreachable!("version given"))
+        };
+        let shell = override_first.detect_with(same).unwrap();
+        assert_eq!(shell.version(), Some("4.0.0".to_string()));
+        // Unset, detection goes on; unsupported, it fails.
+        let shell = override_first
+            .detect_with(sys(read_detect_run_err, run_detect_ok))
+            .unwrap();
+        assert_eq!(shell.source(), Source::Process);
+        let unsupported = Sys {
+            var: |key| (key == "SHELLVER_SHELL").then(|| "python3".to_string()),
+            ..sys(read_walk_not_found, run_env_shell)
+        };
+        let err = override_first.detect_with(unsupported).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(Source::Override.as_str(), "override");
+    }
+
+    #[test]
+    fn named_shell_is_bare() {
+        let shell = Shell::named("sh");
+        assert_eq!(shell.name(), "sh");
+        assert_eq!(shell.version(), None);
+        assert_eq!(shell.path(), None);
+        assert_eq!(shell.source(), Source::Default);
+        assert_eq!(Source::Default.as_str(), "default");
+    }
+
+    #[test]
+    fn detect_with_proc_walk_source() {
+        let shell = Detector::new()
+            .detect_with(sys(read_detect_run_err, run_detect_ok))
+            .unwrap();
+        assert_eq!(shell.source(), Source::Process);
+        assert_eq!(shell.warnings(), [Warning::VersionFromPath]);
+    }
+
+    fn read_ancestry(path: &str) -> io::Result<String> {
+        match path {
+            "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+            "/proc/100/environ" => Ok("PATH=/bin\0".to_string()),
+            "/proc/100/status" => Ok("PPid:\t200\n".to_string()),
+            "/proc/200/environ" => Err(io::Error::new(io::ErrorKind::PermissionDenied, "deny")),
+            "/proc/200/status" => Ok("PPid:\t300\n".to_string()),
+            "/proc/300/environ" => Ok("SSH_AUTH_SOCK=/tmp/agent.1\0".to_string()),
+            "/proc/300/status" => Ok("PPid:\t1\n".to_string()),
+            "/proc/1/environ" => Ok("HOME=/\0".to_string()),
+            _ => unreachable!("bad path"),
+        }
+    }
+
+    #[test]
+    fn find_env_in_ancestry_found() {
+        let sys = sys(read_ancestry, run_detect_no_ps);
+        let val = Detector::new().find_env_in_ancestry_with("SSH_AUTH_SOCK", sys);
+        assert_eq!(val.unwrap(), Some("/tmp/agent.1".to_string()));
+    }
+
+    #[test]
+    fn find_env_in_ancestry_missing() {
+        let sys = sys(read_ancestry, run_detect_no_ps);
+        let val = Detector::new().find_env_in_ancestry_with("TMUX", sys);
+        assert_eq!(val.unwrap(), None);
+    }
+
+    fn read_login(path: &str) -> io::Result<String> {
+        match path {
+            "/proc/self/status" => {
+                Ok("Name:\tshellver\nUid:\t1000\t1000\t1000\t1000\n".to_string())
+            }
+            "/etc/passwd" => Ok("root:x:0:0::/root:/bin/bash\n\
+                 alice:x:1000:1000::/home/alice:/usr/bin/zsh\n\
+                 svc:x:999:999::/:/usr/sbin/nologin\n"
+                .to_string()),
+            _ => unreachable!("bad path"),
+        }
+    }
+
+    #[test]
+    fn detect_login_shell_ok() {
+        fn run_login(name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+            if name == GETENT {
+                return Ok(Vec::new());
+            }
+            assert_eq!(name, "/usr/bin/zsh");
+            Ok(b"zsh 5.9 (x86_64-pc-linux-gnu)".to_vec())
+        }
+
+        let shell = Detector::new()
+            .detect_login_shell_with(sys(read_login, run_login))
+            .unwrap();
+        assert_eq!(shell.name(), "zsh");
+        assert_eq!(shell.version(), Some("5.9".to_string()));
+        assert_eq!(shell.source(), Source::Passwd);
+    }
+
+    #[test]
+    fn detect_login_shell_unsupported() {
+        fn read_nologin(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("Uid:\t999\t999\t999\t999\n".to_string()),
+                _ => read_login(path),
+            }
+        }
+
+        let err = Detector::new()
+            .detect_login_shell_with(sys(read_nologin, run_detect_ok))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    fn run_login_of(name: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+        match (name, args) {
+            (GETENT, ["passwd", "ldapuser"]) => {
+                Ok(b"ldapuser:*:5000:5000::/home/ldapuser:/bin/bash\n".to_vec())
+            }
+            (GETENT, _) => Ok(Vec::new()),
+            ("/bin/bash", _) => Ok(b"GNU bash, version 5.2.15(1)-release".to_vec()),
+            ("/usr/bin/zsh", _) => Ok(b"zsh 5.9".to_vec()),
+            _ => unreachable!("bad command"),
+        }
+    }
+
+    #[test]
+    fn login_shell_of_getent() {
+        fn read_never(_path: &str) -> io::Result<String> {
+            unreachable!("getent answered");
+        }
+
+        let shell = Detector::new()
+            .login_shell_of_with("ldapuser", sys(read_never, run_login_of))
+            .unwrap();
+        assert_eq!(shell.name(), "bash");
+        assert_eq!(shell.version(), Some("5.2.15".to_string()));
+    }
+
+    #[test]
+    fn detect_login_shell_getent() {
+        fn read_status(path: &str) -> io::Result<String> {
+            assert_eq!(path, "/proc/self/status");
+            Ok("Uid:\t5000\t5000\t5000\t5000\n".to_string())
+        }
+
+        fn run_uid(name: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+            match (name, args) {
+                (GETENT, ["passwd", "5000"]) => run_login_of(name, &["passwd", "ldapuser"]),
+                _ => run_login_of(name, args),
+            }
+        }
+
+        let shell = Detector::new()
+            .detect_login_shell_with(sys(read_status, run_uid))
+            .unwrap();
+        assert_eq!(shell.name(), "bash");
+        assert_eq!(shell.uid(), Some(5000));
+    }
+
+    #[test]
+    fn login_shell_of_passwd_file() {
+        fn read_host(path: &str) -> io::Result<String> {
+            assert_eq!(path, "/host/etc/passwd");
+            Ok("alice:x:1000:1000::/home/alice:/bin/bash\n".to_string())
+        }
+
+        let shell = Detector::new()
+            .passwd_file("/host/etc/passwd")
+            .login_shell_of_with("alice", sys(read_host, run_login_of))
+            .unwrap();
+        assert_eq!(shell.name(), "bash");
+    }
+
+    #[test]
+    fn login_shell_of_package_version() {
+        fn run_packages(name: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+            match (name, args) {
+                (GETENT, _) => Ok(b"alice:x:1000:1000::/home/alice:/bin/dash\n".to_vec()),
+                ("dpkg-query", _) => Err(io::Error::new(io::ErrorKind::NotFound, "no dpkg")),
+                ("rpm", [.., "dash"]) => Ok(b"package dash is not installed\n".to_vec()),
+                ("pacman", ["-Q", "dash"]) => Ok(b"dash 0.5.12-1\n".to_vec()),
+                _ => unreachable!("bad command"),
+            }
+        }
+
+        let sys = sys(read_login, run_packages);
+        let shell = Detector::new().login_shell_of_with("alice", sys).unwrap();
+        assert_eq!(shell.version(), None);
+        let shell = Detector::new()
+            .package_versions(true)
+            .login_shell_of_with("alice", sys)
+            .unwrap();
+        assert_eq!(shell.name(), "dash");
+        assert_eq!(shell.version(), Some("0.5.12".to_string()));
+    }
+
+    #[test]
+    fn login_shell_of_embedded_version() {
+        fn run_getent(name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+            assert_eq!(name, GETENT);
+            Ok(b"alice:x:1000:1000::/home/alice:/bin/dash\n".to_vec())
+        }
+
+        let sys = Sys {
+            read_bytes: |path| {
+                assert_eq!(path, "/bin/dash");
+                Ok(b"\x7fELF\0GLIBC_2.34\0dash 0.5.12\0".to_vec())
+            },
+            ..sys(read_login, run_getent)
+        };
+        let shell = Detector::new().login_shell_of_with("alice", sys).unwrap();
+        assert_eq!(shell.version(), Some("0.5.12".to_string()));
+    }
+
+    #[test]
+    fn login_shell_of_passwd_fallback() {
+        let shell = Detector::new()
+            .login_shell_of_with("alice", sys(read_login, run_login_of))
+            .unwrap();
+        assert_eq!(shell.name(), "zsh");
+        assert_eq!(shell.source(), Source::Passwd);
+    }
+
+    #[test]
+    fn login_shell_of_unknown_user() {
+        let err = Detector::new()
+            .login_shell_of_with("bob", sys(read_login, run_login_of))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn recommended_invocation_per_shell() {
+        let argv = |name, version: Option<&str>| {
+            let mut shell = Shell::new(name, Source::Process);
+            shell.version = version.map(str::to_string);
+            shell.recommended_invocation()
+        };
+        assert_eq!(argv("bash", None), ["bash", "--noprofile", "--norc", "-c"]);
+        assert_eq!(argv("dash", None), ["dash", "-c"]);
+        assert_eq!(argv("busybox", None), ["busybox", "sh", "-c"]);
+        assert_eq!(argv("fish", Some("3.7.1")), ["fish", "--no-config", "-c"]);
+        assert_eq!(argv("fish", Some("3.5.1")), ["fish", "-c"]);
+        let mut zsh = Shell::new("zsh", Source::Process);
+        zsh.path = Some("/opt/homebrew/bin/zsh".into());
+        assert_eq!(
+            zsh.recommended_invocation(),
+            ["/opt/homebrew/bin/zsh", "-f", "-c"]
+        );
+    }
+
+    #[test]
+    fn fingerprint_per_session() {
+        let shell = |tty: &str, pid| {
+            let mut shell = Shell::new("bash", Source::Process);
+            shell.path = Some("/usr/bin/bash".into());
+            shell.version = Some("5.2.15".to_string());
+            shell.args = vec!["-bash".to_string()];
+            shell.tty = Some(tty.into());
+            shell.pid = Some(pid);
+            shell.fingerprint()
+        };
+        assert_eq!(shell("/dev/pts/3", 100), "951c209b95752dfd");
+        assert_eq!(shell("/dev/pts/3", 4242), shell("/dev/pts/3", 100));
+        assert_ne!(shell("/dev/pts/4", 100), shell("/dev/pts/3", 100));
+    }
+
+    #[test]
+    fn reload_command_per_shell() {
+        let reload = |name| Shell::new(name, Source::Process).reload_command();
+        assert_eq!(reload("bash"), "exec bash -l");
+        assert_eq!(reload("zsh"), "source ~/.zshrc");
+        assert_eq!(reload("fish"), "exec fish");
+        assert_eq!(reload("yash"), ". ~/.yashrc");
+        assert_eq!(reload("dash"), ". ~/.profile");
+        assert_eq!(reload("pwsh"), ". $PROFILE");
+    }
+
+    #[test]
+    fn with_extensions_follows_pathext() {
+        assert_eq!(with_extensions("pwsh", None), ["pwsh"]);
+        assert_eq!(
+            with_extensions("pwsh", Some(".COM;.EXE;;.CMD;bat")),
+            ["pwsh.com", "pwsh.exe", "pwsh.cmd"]
+        );
+        assert_eq!(
+            shell_from_file(Path::new("/mnt/c/Program Files/PowerShell/7/pwsh.EXE")),
+            Some("pwsh")
+        );
+        assert_eq!(shell_from_file(Path::new("/bin/bash.old")), None);
+    }
+
+    #[test]
+    fn installed_dedups_shells_file_and_path() {
+        fn read_shells(path: &str) -> io::Result<String> {
+            assert_eq!(path, "/etc/shells");
+            Ok(
+                "# /etc/shells\n/bin/bash\n/usr/bin/bash\n/bin/zsh\n/usr/bin/git-shell\n"
+                    .to_string(),
+            )
+        }
+
+        fn run_installed(name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+            match name {
+                "/bin/bash" => Ok(b"GNU bash, version 5.2.15(1)-release".to_vec()),
+                "/opt/fish/bin/fish" => Ok(b"fish, version 3.7.1".to_vec()),
+                _ => unreachable!("deduplicated or missing"),
+            }
+        }
+
+        let sys = Sys {
+            var: |key| (key == "PATH").then(|| "/usr/bin:/opt/fish/bin".to_string()),
+            is_exec: |path| {
+                ["/bin/bash", "/usr/bin/bash", "/opt/fish/bin/fish"]
+                    .contains(&path.to_str().unwrap())
+            },
+            canonicalize: |path| Ok(PathBuf::from(path.to_str().unwrap().replace("/usr", ""))),
+            ..sys(read_shells, run_installed)
+        };
+        let shells = installed_with(
+            &[PathBuf::from("/etc/shells")],
+            VersionQuery::Banner,
+            sys.run,
+            sys,
+        );
+        let names: Vec<_> = shells.iter().map(Shell::name).collect();
+        assert_eq!(names, ["bash", "fish"]);
+        assert_eq!(shells[0].version(), Some("5.2.15".to_string()));
+        assert_eq!(shells[1].version(), Some("3.7.1".to_string()));
+        assert_eq!(shells[1].source(), Source::Installed);
+    }
+
+    #[test]
+    fn installed_reads_every_shells_file() {
+        fn read_bsd(path: &str) -> io::Result<String> {
+            match path {
+                "/etc/shells" => Ok("/bin/sh\n/bin/csh\n".to_string()),
+                "/usr/local/etc/shells" => Ok("/usr/local/bin/bash # ports\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        let sys = Sys {
+            is_exec: |_| true,
+            ..sys(read_bsd, run_detect_ok)
+        };
+        let files = Detector::new().shells_files;
+        let paths: Vec<_> = installed_with(&files, VersionQuery::Banner, sys.run, sys)
+            .into_iter()
+            .filter_map(|shell| shell.path)
+            .collect();
+        assert!(paths.contains(&PathBuf::from("/usr/local/bin/bash")));
+        assert!(paths.contains(&PathBuf::from("/bin/csh")));
+    }
+
+    #[test]
+    fn listed_shells_default_without_database() {
+        fn read_missing(_path: &str) -> io::Result<String> {
+            Err(io::Error::new(io::ErrorKind::NotFound, "missing"))
+        }
+
+        let sys = sys(read_missing, run_detect_ok);
+        let files = [PathBuf::from("/missing/shells")];
+        assert_eq!(
+            listed_shells_with(&files, sys),
+            DEFAULT_SHELLS.map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn installations_path_order_then_prefixes() {
+        fn run_zsh(name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+            match name {
+                "/usr/bin/zsh" => Ok(b"zsh 5.9 (x86_64-pc-linux-gnu)".to_vec()),
+                "/opt/homebrew/bin/zsh" => Ok(b"zsh 5.9.1 (arm64-apple-darwin)".to_vec()),
+                "/home/alice/.nix-profile/bin/zsh" => Ok(b"zsh 5.8".to_vec()),
+                _ => unreachable!("deduplicated or missing"),
+            }
+        }
+
+        let sys = Sys {
+            var: |key| match key {
+                "PATH" => Some("/usr/bin:/bin:/usr/local/bin".to_string()),
+                "HOME" => Some("/home/alice".to_string()),
+                _ => None,
+            },
+            is_exec: |path| {
+                let path = path.to_str().unwrap();
+                path != "/usr/local/bin/zsh" && path.ends_with("/zsh")
+            },
+            canonicalize: |path| Ok(PathBuf::from(path.to_str().unwrap().replace("/usr", ""))),
+            ..sys(read_detect_run_err, run_zsh)
+        };
+        let shells = installations_with("zsh", VersionQuery::Banner, sys.run, sys).unwrap();
+        let paths: Vec<_> = shells.iter().filter_map(Shell::path).collect();
+        assert_eq!(
+            paths,
+            [
+                Path::new("/usr/bin/zsh"),
+                Path::new("/opt/homebrew/bin/zsh"),
+                Path::new("/home/alice/.nix-profile/bin/zsh"),
+            ]
+        );
+        let versions: Vec<_> = shells.iter().filter_map(Shell::version).collect();
+        assert_eq!(versions, ["5.9", "5.9.1", "5.8"]);
+        let err = installations_with("git-shell", VersionQuery::Banner, sys.run, sys).unwrap_err();
         assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
     }
+
+    #[test]
+    fn detect_with_probe() {
+        fn run_probe(name: &str, args: &[&str], timeout: Duration) -> io::Result<Vec<u8>> {
+            assert_eq!(name, "bash");
+            assert_eq!(timeout, Duration::from_millis(500));
+            match args {
+                ["-c", "a=(x y); echo ok"] => Ok(b"ok\n".to_vec()),
+                ["-c", "cat <(echo ok)"] => Err(io::Error::new(io::ErrorKind::TimedOut, "slow")),
+                _ => Ok(Vec::new()),
+            }
+        }
+
+        let sys = Sys {
+            run_timeout: run_probe,
+            ..sys(read_detect_run_err, run_detect_ok)
+        };
+        let detector = Detector::new()
+            .probe(true)
+            .timeout(Duration::from_millis(500));
+        let shell = detector.detect_with(sys).unwrap();
+        assert_eq!(shell.capabilities(), [Capability::Arrays]);
+    }
+
+    #[test]
+    fn detect_with_static_capabilities() {
+        let shell = Detector::new()
+            .detect_with(sys(read_detect_run_err, run_detect_ok))
+            .unwrap();
+        assert!(
+            shell
+                .capabilities()
+                .contains(&Capability::AssociativeArrays)
+        );
+    }
+
+    #[test]
+    fn detect_with_exec_forbidden() {
+        fn run_never(_name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+            unreachable!("exec forbidden");
+        }
+
+        let detector = Detector::new().allow_exec(false).probe(true);
+        let shell = detector
+            .detect_with(sys(read_detect_run_err, run_never))
+            .unwrap();
+        assert_eq!(shell.name(), "bash");
+        assert_eq!(shell.version(), None);
+        assert_eq!(shell.capabilities(), capability::of("bash"));
+    }
+
+    #[test]
+    fn run_cmd_timeout_kills_slow_command() {
+        let err = run_cmd_timeout("sleep", &["5"], Duration::from_millis(50)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        let out = run_cmd_timeout("echo", &["ok"], TIMEOUT).unwrap();
+        assert_eq!(out, b"ok\n");
+    }
+
+    #[test]
+    fn run_cmd_keeps_terminal_away() {
+        // `cat` would block on an inherited terminal, a null stdin ends it.
+        assert!(run_cmd("cat", &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn run_cmd_clean_environment() {
+        let out = run_cmd("sh", &["-c", "echo \"$LC_ALL $LANG ${LANGUAGE-unset}\""]).unwrap();
+        assert_eq!(out, b"C C unset\n");
+    }
+
+    #[test]
+    fn run_cmd_falls_back_to_stderr() {
+        let out = run_cmd("sh", &["-c", "echo banner >&2; exit 2"]).unwrap();
+        assert_eq!(out, b"banner\n");
+        let out = run_cmd("sh", &["-c", "echo out; echo err >&2"]).unwrap();
+        assert_eq!(out, b"out\n");
+    }
+
+    #[test]
+    fn version_of_name_and_path() {
+        fn run_version_of(name: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+            match (name, args) {
+                ("zsh", ["--version"]) => Ok(b"zsh 5.9 (x86_64-pc-linux-gnu)".to_vec()),
+                ("nu", ["--version"]) => Ok(b"0.101.0\n".to_vec()),
+                ("pwsh", ["--version"]) => Ok(b"PowerShell 7.4.1\n".to_vec()),
+                ("es", ["-c", _]) => Ok(b"es version 0.9.2 2-Mar-2022\n".to_vec()),
+                ("/opt/bin/mksh", ["-c", _]) => Ok(b"@(#)MIRBSD KSH R59 2020/10/31".to_vec()),
+                ("ksh", ["-c", script]) if script.contains("KSH_VERSION") => {
+                    Ok(b"Version AJM 93u+m/1.0.8 2024-01-01".to_vec())
+                }
+                ("/bin/csh", ["-c", "echo $version"]) => {
+                    Ok(b"tcsh 6.21.00 (Astron) 2019-05-08 (unknown-apple-darwin)\n".to_vec())
+                }
+                _ => Err(io::Error::new(io::ErrorKind::NotFound, "not installed")),
+            }
+        }
+
+        let val = version_of_with("zsh", VersionQuery::Banner, run_version_of).unwrap();
+        assert_eq!(val, Some("5.9".to_string()));
+        let val = version_of_with("nu", VersionQuery::Banner, run_version_of).unwrap();
+        assert_eq!(val, Some("0.101.0".to_string()));
+        let val = version_of_with("pwsh", VersionQuery::Banner, run_version_of).unwrap();
+        assert_eq!(val, Some("7.4.1".to_string()));
+        let val = version_of_with("es", VersionQuery::Banner, run_version_of).unwrap();
+        assert_eq!(val, Some("0.9.2".to_string()));
+        let val = version_of_with(
+            "/usr/lib/plan9/bin/rc",
+            VersionQuery::Banner,
+            run_version_of,
+        )
+        .unwrap();
+        assert_eq!(val, None);
+        let val = version_of_with("/opt/bin/mksh", VersionQuery::Banner, run_version_of).unwrap();
+        assert_eq!(val, Some("R59".to_string()));
+        let val = version_of_with("ksh", VersionQuery::Banner, run_version_of).unwrap();
+        assert_eq!(val, Some("1.0.8".to_string()));
+        let val = version_of_with("/bin/csh", VersionQuery::Banner, run_version_of).unwrap();
+        assert_eq!(val, Some("6.21.00".to_string()));
+        let val = version_of_with("/usr/bin/csh", VersionQuery::Banner, run_version_of).unwrap();
+        assert_eq!(val, None);
+        let val = version_of_with("/bin/dash", VersionQuery::Banner, run_version_of).unwrap();
+        assert_eq!(val, None);
+        let err = version_of_with("fish", VersionQuery::Banner, run_version_of).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn version_of_builtin_variable() {
+        fn run_builtin(name: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+            match (name, args) {
+                ("bash", [.., script]) if script.contains("BASH_VERSION") => {
+                    Ok(b"5.2.15(1)-release".to_vec())
+                }
+                // An elvish without `$buildinfo` leaves the variable empty.
+                ("elvish", [.., script]) if script.contains("buildinfo") => Ok(Vec::new()),
+                ("elvish", ["--version"]) => Ok(b"0.14.1\n".to_vec()),
+                _ => unreachable!("bad command"),
+            }
+        }
+
+        let val = version_of_with("bash", VersionQuery::Builtin, run_builtin).unwrap();
+        assert_eq!(val, Some("5.2.15".to_string()));
+        let val = version_of_with("elvish", VersionQuery::Builtin, run_builtin).unwrap();
+        assert_eq!(val, Some("0.14.1".to_string()));
+        let val = version_of_with("dash", VersionQuery::Builtin, run_builtin).unwrap();
+        assert_eq!(val, None);
+    }
+
+    fn read_nested(path: &str) -> io::Result<String> {
+        match path {
+            "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+            "/proc/100/comm" => Ok("zsh\n".to_string()),
+            "/proc/100/status" => Ok("PPid:\t200\n".to_string()),
+            "/proc/200/comm" => Ok("tmux: server\n".to_string()),
+            "/proc/200/status" => Ok("PPid:\t300\n".to_string()),
+            "/proc/300/comm" => Ok("bash\n".to_string()),
+            "/proc/300/status" => Ok("PPid:\t1\n".to_string()),
+            _ => unreachable!("bad path"),
+        }
+    }
+
+    fn run_nested(name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+        match name {
+            "zsh" => Ok(b"zsh 5.9".to_vec()),
+            "bash" => Ok(b"GNU bash, version 5.2.15(1)-release".to_vec()),
+            _ => unreachable!("bad command"),
+        }
+    }
+
+    #[test]
+    fn detect_all_nearest_first() {
+        let shells = Detector::new()
+            .detect_all_with(sys(read_nested, run_nested))
+            .unwrap();
+        let names: Vec<_> = shells.iter().map(Shell::name).collect();
+        assert_eq!(names, ["zsh", "bash"]);
+        assert_eq!(shells[0].pid(), Some(100));
+        assert_eq!(shells[0].ppid(), Some(200));
+        assert_eq!(shells[1].pid(), Some(300));
+        assert_eq!(shells[1].ppid(), Some(1));
+    }
+
+    #[test]
+    fn ancestors_mark_shells() {
+        fn run_none(_name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+            unreachable!("no version is asked for")
+        }
+
+        let ancestors = Detector::new()
+            .ancestors_with(sys(read_nested, run_none))
+            .unwrap();
+        let hops: Vec<_> = ancestors
+            .iter()
+            .map(|hop| (hop.pid(), hop.comm(), hop.shell()))
+            .collect();
+        assert_eq!(
+            hops,
+            [
+                (100, "zsh", Some("zsh")),
+                (200, "tmux: server", None),
+                (300, "bash", Some("bash")),
+            ]
+        );
+    }
+
+    #[test]
+    fn detect_all_inherited_version() {
+        fn run_outer(name: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+            assert_ne!(name, "zsh", "version taken from the environment");
+            run_nested(name, args)
+        }
+
+        let sys = Sys {
+            var: |key| match key {
+                "ZSH_VERSION" => Some("5.8.1".to_string()),
+                "BASH_VERSION" => Some("4.4.20(1)-release".to_string()),
+                _ => None,
+            },
+            ..sys(read_nested, run_outer)
+        };
+        let shells = Detector::new().detect_all_with(sys).unwrap();
+        assert_eq!(shells[0].version(), Some("5.8.1".to_string()));
+        // The outer bash's variable may be stale, its binary is asked.
+        assert_eq!(shells[1].version(), Some("5.2.15".to_string()));
+    }
+
+    #[test]
+    fn detect_inherited_version_of_other_shell() {
+        let sys = Sys {
+            var: |key| (key == "FISH_VERSION").then(|| "3.7.1".to_string()),
+            ..sys(read_nested, run_nested)
+        };
+        let shell = Detector::new().detect_with(sys).unwrap();
+        assert_eq!(shell.name(), "zsh");
+        assert_eq!(shell.version(), Some("5.9".to_string()));
+    }
+
+    #[test]
+    fn wrappers_do_not_use_up_hops() {
+        // 40 nested `sudo` processes, more than the hop limit, below a bash.
+        fn read_wrapped(path: &str) -> io::Result<String> {
+            let (pid, file) = match path {
+                "/proc/self/status" => return Ok("PPid:\t1000\n".to_string()),
+                path => path[6..].split_once('/').unwrap(),
+            };
+            let pid: u32 = pid.parse().unwrap();
+            match (file, pid) {
+                ("comm", 1040) => Ok("bash\n".to_string()),
+                ("comm", _) => Ok("sudo\n".to_string()),
+                ("status", 1040) => Ok("PPid:\t1\n".to_string()),
+                ("status", _) => Ok(format!("PPid:\t{}\n", pid + 1)),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        let sys = sys(read_wrapped, run_detect_ok);
+        let shell = Detector::new().detect_with(sys).unwrap();
+        assert_eq!(shell.pid(), Some(1040));
+        let err = Detector::new()
+            .skip_processes(["doas"])
+            .detect_with(sys)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let not_found: &ShellNotFound = err.get_ref().unwrap().downcast_ref().unwrap();
+        assert_eq!(not_found.end, ChainEnd::HopLimit);
+    }
+
+    #[test]
+    fn detect_all_elevated_via() {
+        // `sudo su` run from a zsh, which started a root bash.
+        fn read_elevated(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/proc/100/comm" => Ok("bash\n".to_string()),
+                "/proc/100/status" => Ok("PPid:\t200\n".to_string()),
+                "/proc/200/comm" => Ok("su\n".to_string()),
+                "/proc/200/status" => Ok("PPid:\t300\n".to_string()),
+                "/proc/300/comm" => Ok("sudo\n".to_string()),
+                "/proc/300/status" => Ok("PPid:\t400\n".to_string()),
+                "/proc/400/comm" => Ok("zsh\n".to_string()),
+                "/proc/400/status" => Ok("PPid:\t1\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        let shells = Detector::new()
+            .detect_all_with(sys(read_elevated, run_detect_ok))
+            .unwrap();
+        let via: Vec<_> = shells.iter().map(Shell::elevated_via).collect();
+        assert_eq!(via, [None, Some(Elevation::Su)]);
+        assert_eq!(Elevation::Su.as_str(), "su");
+    }
+
+    #[test]
+    fn not_found_reports_chain() {
+        fn read_orphan(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/proc/100/comm" => Ok("cron\n".to_string()),
+                "/proc/100/status" => Ok("PPid:\t200\n".to_string()),
+                "/proc/200/comm" => Ok("systemd\n".to_string()),
+                "/proc/200/status" => Ok("PPid:\t1\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+        fn read_init(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/200/comm" => Ok("sshd\n".to_string()),
+                path => read_orphan(path),
+            }
+        }
+        fn not_found(err: &io::Error) -> &ShellNotFound {
+            assert_eq!(err.kind(), io::ErrorKind::NotFound);
+            err.get_ref().unwrap().downcast_ref().unwrap()
+        }
+
+        let err = Detector::new()
+            .detect_with(sys(read_orphan, run_detect_ok))
+            .unwrap_err();
+        let orphan = not_found(&err);
+        assert!(!orphan.nested_pid_namespace);
+        assert_eq!(
+            orphan.chain,
+            [(100, "cron".to_string()), (200, "systemd".to_string())]
+        );
+        assert_eq!(orphan.end, ChainEnd::SessionManager("systemd".to_string()));
+        assert_eq!(
+            err.to_string(),
+            "shell not found, reached session manager systemd after cron (100), systemd (200)"
+        );
+
+        let err = Detector::new()
+            .detect_with(sys(read_init, run_detect_ok))
+            .unwrap_err();
+        assert_eq!(not_found(&err).end, ChainEnd::Init);
+    }
+
+    #[test]
+    fn not_found_in_container() {
+        fn read_container(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t1\nNSpid:\t4242\t7\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        let err = Detector::new()
+            .detect_with(sys(read_container, run_detect_ok))
+            .unwrap_err();
+        let not_found: &ShellNotFound = err.get_ref().unwrap().downcast_ref().unwrap();
+        assert!(not_found.nested_pid_namespace);
+        assert_eq!(not_found.end, ChainEnd::Init);
+        assert_eq!(
+            err.to_string(),
+            "shell not found, reached init; running in a nested PID namespace, try the host's /proc as proc root"
+        );
+    }
+
+    #[test]
+    fn detect_all_skips_subshell_forks() {
+        fn read_forks(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/proc/100/comm" | "/proc/200/comm" | "/proc/300/comm" => Ok("bash\n".to_string()),
+                "/proc/100/status" => Ok("PPid:\t200\n".to_string()),
+                "/proc/200/status" => Ok("PPid:\t300\n".to_string()),
+                "/proc/300/status" => Ok("PPid:\t1\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+        // 100 is a fork of 200, which was started from 300 by typing `bash`.
+        fn stat_forks(path: &str) -> io::Result<Vec<u8>> {
+            let arg_start = match path {
+                "/proc/100/stat" | "/proc/200/stat" => 7000,
+                "/proc/300/stat" => 9000,
+                _ => unreachable!("bad path"),
+            };
+            let stat = format!("1 (bash) S 1{} {arg_start} 0 0 0", " 0".repeat(43));
+            Ok(stat.into_bytes())
+        }
+
+        let forks = Sys {
+            read_bytes: |path| match path.strip_suffix("/cmdline") {
+                Some(_) => Ok(b"bash\0".to_vec()),
+                None => stat_forks(path),
+            },
+            ..sys(read_forks, run_detect_ok)
+        };
+        let shells = Detector::new().detect_all_with(forks).unwrap();
+        let pids: Vec<_> = shells.iter().filter_map(Shell::pid).collect();
+        assert_eq!(pids, [200, 300]);
+
+        // Without access to the addresses nothing is merged.
+        let hidden = Sys {
+            read_bytes: |path| match path.strip_suffix("/cmdline") {
+                Some(_) => Ok(b"bash\0".to_vec()),
+                None => Err(io::Error::from(io::ErrorKind::PermissionDenied)),
+            },
+            ..forks
+        };
+        let shells = Detector::new().detect_all_with(hidden).unwrap();
+        assert_eq!(shells.len(), 3);
+    }
+
+    #[test]
+    fn session_strategy_finds_sibling_shell() {
+        // sshd (10) started a forced command whose bash (20) sits next to us.
+        fn read_session(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/stat" => Ok("50 (shellver) S 10 50 10 0".to_string()),
+                "/proc/self/status" | "/proc/20/status" => Ok("PPid:\t10\n".to_string()),
+                "/proc/10/comm" => Ok("sshd\n".to_string()),
+                "/proc/10/status" => Ok("PPid:\t1\n".to_string()),
+                "/proc/10/stat" => Ok("10 (sshd) S 1 10 10 0".to_string()),
+                "/proc/20/stat" => Ok("20 (bash) S 10 20 10 0".to_string()),
+                "/proc/30/stat" => Ok("30 (zsh) S 1 30 30 0".to_string()),
+                _ => Err(io::Error::new(io::ErrorKind::NotFound, "gone")),
+            }
+        }
+
+        let sys = Sys {
+            list: |_| Ok(["self", "10", "20", "30", "40"].map(String::from).to_vec()),
+            ..sys(read_session, run_detect_ok)
+        };
+        let err = Detector::new().detect_with(sys).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let shell = Detector::new()
+            .strategies([Strategy::ProcWalk, Strategy::Session])
+            .detect_with(sys)
+            .unwrap();
+        assert_eq!(shell.name(), "bash");
+        assert_eq!(shell.pid(), Some(20));
+        assert_eq!(shell.ppid(), Some(10));
+    }
+
+    #[test]
+    fn survey_sorted_by_user_then_pid() {
+        fn read_survey(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/7/comm" | "/proc/42/comm" | "/proc/9/comm" => Ok("bash\n".to_string()),
+                "/proc/5/comm" => Ok("zsh\n".to_string()),
+                "/proc/8/comm" => Ok("sshd\n".to_string()),
+                "/proc/7/status" => Ok("PPid:\t3\nUid:\t1000\t1000\n".to_string()),
+                "/proc/5/status" => Ok("Uid:\t1000\t1000\n".to_string()),
+                "/proc/42/status" => Ok("Uid:\t0\t0\n".to_string()),
+                _ => Err(io::Error::new(io::ErrorKind::NotFound, "gone")),
+            }
+        }
+
+        let sys = Sys {
+            list: |_| {
+                Ok(["self", "7", "42", "5", "8", "9"]
+                    .map(String::from)
+                    .to_vec())
+            },
+            ..sys(read_survey, run_nested)
+        };
+        let shells = Detector::new().survey_with(sys).unwrap();
+        let pids: Vec<_> = shells.iter().map(|shell| shell.pid.unwrap()).collect();
+        assert_eq!(pids, [42, 5, 7, 9]);
+        assert_eq!(shells[2].ppid(), Some(3));
+        assert_eq!(shells[1].ppid(), None);
+        assert_eq!(shells[1].version(), Some("5.9".to_string()));
+    }
+
+    #[test]
+    fn version_cmp_numeric() {
+        assert_eq!(version_cmp(Some("5.10"), Some("5.9")), Ordering::Greater);
+        assert_eq!(version_cmp(Some("R59"), Some("R59")), Ordering::Equal);
+        assert_eq!(version_cmp(None, Some("1.0")), Ordering::Less);
+        assert_eq!(version_cmp(None, None), Ordering::Equal);
+    }
+
+    #[test]
+    fn installed_sorted_by_name_then_version() {
+        fn read_unsorted(_path: &str) -> io::Result<String> {
+            Ok("/bin/zsh\n/opt/new/bash\n/bin/bash\n".to_string())
+        }
+
+        fn run_unsorted(name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+            match name {
+                "/bin/zsh" => Ok(b"zsh 5.9".to_vec()),
+                "/opt/new/bash" => Ok(b"GNU bash, version 5.10.0".to_vec()),
+                _ => Ok(b"GNU bash, version 5.2.15".to_vec()),
+            }
+        }
+
+        let sys = Sys {
+            is_exec: |_| true,
+            ..sys(read_unsorted, run_unsorted)
+        };
+        let shells = installed_with(
+            &[PathBuf::from("/etc/shells")],
+            VersionQuery::Banner,
+            sys.run,
+            sys,
+        );
+        let got: Vec<_> = shells
+            .iter()
+            .map(|shell| (shell.name(), shell.version().unwrap()))
+            .collect();
+        let want = [
+            ("bash", "5.2.15".to_string()),
+            ("bash", "5.10.0".to_string()),
+            ("zsh", "5.9".to_string()),
+        ];
+        assert_eq!(got, want);
+    }
+
+    #[test]
+    fn detect_with_exe_path() {
+        fn run_exe(name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+            assert_eq!(name, "/opt/homebrew/bin/zsh");
+            Ok(b"zsh 5.9".to_vec())
+        }
+
+        fn read_zsh(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/proc/100/comm" => Ok("zsh\n".to_string()),
+                "/proc/100/status" => Ok("PPid:\t1\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        let sys = Sys {
+            read_link: |path| match path {
+                "/proc/100/exe" => Ok(PathBuf::from("/opt/homebrew/bin/zsh")),
+                _ => Err(io::Error::new(io::ErrorKind::PermissionDenied, "deny")),
+            },
+            ..sys(read_zsh, run_exe)
+        };
+        let shell = Detector::new().detect_with(sys).unwrap();
+        assert_eq!(shell.path(), Some(Path::new("/opt/homebrew/bin/zsh")));
+        assert_eq!(shell.version(), Some("5.9".to_string()));
+        assert!(shell.warnings().is_empty());
+    }
+
+    fn read_sh(path: &str) -> io::Result<String> {
+        match path {
+            "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+            "/proc/100/comm" => Ok("sh\n".to_string()),
+            "/proc/100/status" => Ok("PPid:\t1\n".to_string()),
+            _ => unreachable!("bad path"),
+        }
+    }
+
+    #[test]
+    fn detect_with_sh_resolved() {
+        fn run_never(_name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+            unreachable!("dash has no version command");
+        }
+
+        let sys = Sys {
+            read_link: |_| Ok(PathBuf::from("/usr/bin/dash")),
+            ..sys(read_sh, run_never)
+        };
+        let shell = Detector::new().detect_with(sys).unwrap();
+        assert_eq!(shell.name(), "dash");
+        assert_eq!(shell.version(), None);
+        assert!(!shell.posix_mode());
+        assert!(shell.warnings().is_empty());
+    }
+
+    #[test]
+    fn detect_with_sh_implementations() {
+        fn run_impl(name: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+            match (name, args) {
+                ("/usr/bin/bash", ["--version"]) => Ok(b"GNU bash, version 5.2.15(1)".to_vec()),
+                ("/bin/busybox", ["--help"]) => Ok(b"BusyBox v1.36.1 (2024-01-01)".to_vec()),
+                _ => unreachable!("bad command"),
+            }
+        }
+
+        let bash = Sys {
+            read_link: |_| Ok(PathBuf::from("/usr/bin/bash")),
+            ..sys(read_sh, run_impl)
+        };
+        let shell = Detector::new().detect_with(bash).unwrap();
+        assert_eq!(shell.name(), "bash");
+        assert_eq!(shell.version(), Some("5.2.15".to_string()));
+        assert!(shell.posix_mode());
+
+        let busybox = Sys {
+            read_link: |_| Ok(PathBuf::from("/bin/busybox")),
+            ..sys(read_sh, run_impl)
+        };
+        let shell = Detector::new().detect_with(busybox).unwrap();
+        assert_eq!(shell.name(), "busybox");
+        assert_eq!(shell.version(), Some("1.36.1".to_string()));
+        assert!(!shell.posix_mode());
+    }
+
+    #[test]
+    fn detect_with_busybox_ash() {
+        fn read_ash(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/proc/100/comm" => Ok("busybox\n".to_string()),
+                "/proc/100/status" => Ok("PPid:\t1\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+        fn run_busybox(name: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+            assert_eq!((name, args), ("/bin/busybox", ["--help"].as_slice()));
+            Ok(b"BusyBox v1.36.1 (2024-06-10 07:11:47 UTC) multi-call binary.".to_vec())
+        }
+
+        let ash = Sys {
+            read_bytes: |_| Ok(b"busybox\0ash\0".to_vec()),
+            read_link: |path| match path {
+                "/proc/100/exe" => Ok(PathBuf::from("/bin/busybox")),
+                _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+            },
+            ..sys(read_ash, run_busybox)
+        };
+        let shell = Detector::new().detect_with(ash).unwrap();
+        assert_eq!(shell.name(), "ash");
+        assert_eq!(shell.version(), Some("1.36.1".to_string()));
+        assert!(shell.warnings().is_empty());
+    }
+
+    #[test]
+    fn detect_with_truncated_comm() {
+        fn read_long(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/proc/100/comm" => Ok("powershell-prev\n".to_string()),
+                "/proc/100/status" => Ok("PPid:\t1\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        let exe = Sys {
+            read_link: |path| match path {
+                "/proc/100/exe" => Ok(PathBuf::from("/opt/microsoft/powershell/7/pwsh")),
+                _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+            },
+            ..sys(read_long, run_detect_ok)
+        };
+        let shell = Detector::new().detect_with(exe).unwrap();
+        assert_eq!(shell.name(), "pwsh");
+        assert!(shell.warnings().is_empty());
+
+        let argv0 = Sys {
+            read_bytes: |_| Ok(b"/usr/local/bin/pwsh\0-NoLogo\0".to_vec()),
+            ..sys(read_long, run_detect_ok)
+        };
+        let shell = Detector::new().detect_with(argv0).unwrap();
+        assert_eq!(shell.name(), "pwsh");
+    }
+
+    #[test]
+    fn detect_with_rc_by_exe() {
+        fn read_rc(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/proc/100/comm" => Ok("rc\n".to_string()),
+                "/proc/100/status" => Ok("PPid:\t200\n".to_string()),
+                "/proc/200/comm" => Ok("zsh\n".to_string()),
+                "/proc/200/status" => Ok("PPid:\t1\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        fn run_no_version(_name: &str, _args: &[&str]) -> io::Result<Vec<u8>> {
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        }
+
+        let plan9 = Sys {
+            read_link: |path| match path {
+                "/proc/100/exe" => Ok(PathBuf::from("/usr/lib/plan9/bin/rc")),
+                _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+            },
+            ..sys(read_rc, run_no_version)
+        };
+        let shell = Detector::new().detect_with(plan9).unwrap();
+        assert_eq!(shell.name(), "rc");
+        assert_eq!(shell.version(), None);
+
+        let init_script = Sys {
+            read_link: |path| match path {
+                "/proc/100/exe" => Ok(PathBuf::from("/bin/dash")),
+                _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+            },
+            ..sys(read_rc, run_detect_ok)
+        };
+        let shell = Detector::new().detect_with(init_script).unwrap();
+        assert_eq!(shell.name(), "zsh");
+    }
+
+    #[test]
+    fn detect_with_python_xonsh() {
+        fn read_xonsh(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/proc/100/comm" => Ok("xonsh\n".to_string()),
+                "/proc/100/status" => Ok("PPid:\t1\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        fn read_python(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/100/comm" => Ok("python3\n".to_string()),
+                _ => read_xonsh(path),
+            }
+        }
+
+        fn run_xonsh(name: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+            match (name, args) {
+                ("/usr/bin/xonsh", ["--version"]) => Ok(b"xonsh/0.15.1\n".to_vec()),
+                ("xonsh", ["--version"]) => Ok(b"xonsh/0.14.0\n".to_vec()),
+                _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+            }
+        }
+
+        fn python_exe(path: &str) -> io::Result<PathBuf> {
+            match path {
+                "/proc/100/exe" => Ok(PathBuf::from("/usr/bin/python3.12")),
+                _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+            }
+        }
+
+        let shebang = Sys {
+            read_bytes: |_| Ok(b"/usr/bin/python3\0/usr/bin/xonsh\0--login\0".to_vec()),
+            read_link: python_exe,
+            ..sys(read_xonsh, run_xonsh)
+        };
+        let shell = Detector::new().detect_with(shebang).unwrap();
+        assert_eq!(shell.name(), "xonsh");
+        assert_eq!(shell.version(), Some("0.15.1".to_string()));
+        assert_eq!(shell.path(), Some(Path::new("/usr/bin/xonsh")));
+        assert!(shell.warnings().is_empty());
+
+        let module = Sys {
+            read_bytes: |_| Ok(b"python3\0-I\0-m\0xonsh\0".to_vec()),
+            read_link: python_exe,
+            ..sys(read_python, run_xonsh)
+        };
+        let shell = Detector::new().detect_with(module).unwrap();
+        assert_eq!(shell.name(), "xonsh");
+        assert_eq!(shell.version(), Some("0.14.0".to_string()));
+        assert_eq!(shell.warnings(), [Warning::VersionFromPath]);
+
+        let script = ["python3", "/srv/app/manage.py"].map(String::from);
+        assert_eq!(python_shell(&script), None);
+    }
+
+    fn read_renamed(path: &str) -> io::Result<String> {
+        match path {
+            "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+            "/proc/100/comm" => Ok("mytool\n".to_string()),
+            "/proc/100/status" => Ok("PPid:\t200\n".to_string()),
+            "/proc/200/comm" => Ok("zsh\n".to_string()),
+            "/proc/200/status" => Ok("PPid:\t1\n".to_string()),
+            _ => unreachable!("bad path"),
+        }
+    }
+
+    fn exe_bash(path: &str) -> io::Result<PathBuf> {
+        match path {
+            "/proc/100/exe" => Ok(PathBuf::from("/usr/bin/bash")),
+            _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+        }
+    }
+
+    #[test]
+    fn detect_with_renamed_argv0() {
+        // `ln -s /usr/bin/bash mytool; ./mytool` names both comm and argv[0]
+        // after the link.
+        let renamed = Sys {
+            read_bytes: |path| match path {
+                "/proc/100/cmdline" => Ok(b"./mytool\0--norc\0".to_vec()),
+                _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+            },
+            read_link: exe_bash,
+            ..sys(read_renamed, run_detect_ok)
+        };
+        let shell = Detector::new().detect_with(renamed).unwrap();
+        assert_eq!(shell.name(), "bash");
+        assert_eq!(shell.pid(), Some(100));
+        assert_eq!(shell.args(), ["./mytool", "--norc"]);
+    }
+
+    #[test]
+    fn detect_walks_past_scripts() {
+        // A `#!/bin/bash` script keeps the interpreter in argv[0].
+        let script = Sys {
+            read_bytes: |path| match path {
+                "/proc/100/cmdline" => Ok(b"/bin/bash\0./mytool\0".to_vec()),
+                _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+            },
+            read_link: exe_bash,
+            ..sys(read_renamed, run_detect_ok)
+        };
+        let shell = Detector::new().detect_with(script).unwrap();
+        assert_eq!(shell.name(), "zsh");
+        assert_eq!(shell.pid(), Some(200));
+    }
+
+    #[test]
+    fn detect_with_comm_exe_mismatch() {
+        let sys = Sys {
+            read_link: |_| Ok(PathBuf::from("/usr/bin/zsh")),
+            ..sys(read_detect_run_err, run_detect_ok)
+        };
+        let shell = Detector::new().detect_with(sys).unwrap();
+        assert_eq!(shell.name(), "bash");
+        assert_eq!(shell.warnings(), [Warning::CommExeMismatch]);
+    }
+
+    #[test]
+    fn posix_flag_from_args() {
+        let cases: [(&[&str], bool); 4] = [
+            (&["bash", "--posix"], true),
+            (&["bash", "-o", "posix", "-i"], true),
+            (&["bash", "-c", "echo --posix"], false),
+            (&["bash"], false),
+        ];
+        for (args, posix) in cases {
+            let args: Vec<_> = args.iter().map(ToString::to_string).collect();
+            assert_eq!(has_posix_flag(&args), posix, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn detect_with_deleted_exe() {
+        let sys = Sys {
+            read_link: |_| Ok(PathBuf::from("/usr/bin/bash (deleted)")),
+            ..sys(read_detect_run_err, run_detect_ok)
+        };
+        let shell = Detector::new().detect_with(sys).unwrap();
+        assert_eq!(shell.path(), Some(Path::new("/usr/bin/bash")));
+        assert_eq!(shell.warnings(), [Warning::VersionFromPath]);
+    }
+
+    #[test]
+    fn restricted_flag_from_args() {
+        let cases: [(&[&str], bool); 6] = [
+            (&["bash", "-r"], true),
+            (&["bash", "-ir"], true),
+            (&["zsh", "--restricted"], true),
+            (&["zsh", "-o", "restricted"], true),
+            (&["bash", "+r", "--rcfile", "x"], false),
+            (&["bash", "-c", "echo -r"], false),
+        ];
+        for (args, restricted) in cases {
+            let args: Vec<_> = args.iter().map(ToString::to_string).collect();
+            assert_eq!(has_restricted_flag(&args), restricted, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn detect_with_rbash() {
+        fn read_rbash(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/proc/100/comm" => Ok("rbash\n".to_string()),
+                "/proc/100/status" => Ok("PPid:\t1\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        let rbash = Sys {
+            read_bytes: |_| Ok(b"-rbash\0".to_vec()),
+            read_link: |path| match path {
+                "/proc/100/exe" => Ok(PathBuf::from("/usr/bin/bash")),
+                _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+            },
+            ..sys(read_rbash, run_detect_ok)
+        };
+        let shell = Detector::new().detect_with(rbash).unwrap();
+        assert_eq!(shell.name(), "bash");
+        assert!(shell.is_restricted());
+        assert!(shell.is_login());
+        assert!(shell.warnings().is_empty());
+
+        let shell = shell_from_path_with(
+            "/bin/rzsh",
+            Source::Passwd,
+            VersionQuery::Banner,
+            run_detect_ok,
+        )
+        .unwrap();
+        assert!(shell.is_some_and(|shell| shell.name() == "zsh" && shell.is_restricted()));
+        let shell = Detector::new()
+            .detect_with(sys(read_detect_run_err, run_detect_ok))
+            .unwrap();
+        assert!(!shell.is_restricted());
+    }
+
+    #[test]
+    fn detect_with_args() {
+        let sys = Sys {
+            read_bytes: |path| {
+                assert_eq!(path, "/proc/100/cmdline");
+                Ok(b"bash\0--posix\0-i\0".to_vec())
+            },
+            ..sys(read_detect_run_err, run_detect_ok)
+        };
+        let shell = Detector::new().detect_with(sys).unwrap();
+        assert_eq!(shell.args(), ["bash", "--posix", "-i"]);
+    }
+
+    #[test]
+    fn environ_of_detected_shell() {
+        fn read_root(path: &str) -> io::Result<String> {
+            match path {
+                "/host/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/host/proc/100/comm" => Ok("fish\n".to_string()),
+                "/host/proc/100/status" => Ok("PPid:\t1\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        let sys = Sys {
+            read_bytes: |path| match path {
+                "/host/proc/100/environ" => Ok(b"TERM=xterm\0A=b=c\0junk\0".to_vec()),
+                _ => Err(io::Error::new(io::ErrorKind::NotFound, "gone")),
+            },
+            ..sys(read_root, run_detect_ok)
+        };
+        let shell = Detector::new()
+            .proc_root("/host/proc")
+            .detect_with(sys)
+            .unwrap();
+        let environ = shell.environ_with(sys).unwrap();
+        assert_eq!(environ.len(), 2);
+        assert_eq!(environ["TERM"], "xterm");
+        assert_eq!(environ["A"], "b=c");
+    }
+
+    #[test]
+    fn activations_from_environ() {
+        fn read_venv(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/proc/100/comm" => Ok("zsh\n".to_string()),
+                "/proc/100/status" => Ok("PPid:\t1\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        let sys = Sys {
+            read_bytes: |path| match path {
+                "/proc/100/environ" => Ok(b"DIRENV_DIR=-/src/app\0CONDA_DEFAULT_ENV=\0\
+                    VIRTUAL_ENV=/src/app/.venv\0"
+                    .to_vec()),
+                _ => Err(io::Error::new(io::ErrorKind::NotFound, "gone")),
+            },
+            ..sys(read_venv, run_detect_ok)
+        };
+        let shell = Detector::new().detect_with(sys).unwrap();
+        let activations = shell.activations_with(sys).unwrap();
+        assert_eq!(
+            activations,
+            [
+                Activation::Venv(PathBuf::from("/src/app/.venv")),
+                Activation::Direnv(PathBuf::from("/src/app")),
+            ]
+        );
+        let kinds: Vec<_> = activations.iter().map(Activation::kind).collect();
+        assert_eq!(kinds, ["venv", "direnv"]);
+        assert_eq!(activations[1].to_string(), "/src/app");
+    }
+
+    #[test]
+    fn environ_needs_process() {
+        let shell = Shell::new("bash", Source::Environment);
+        let err = shell
+            .environ_with(sys(read_detect_run_err, run_detect_ok))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn cwd_of_detected_shell() {
+        let sys = Sys {
+            read_link: |path| match path {
+                "/proc/100/cwd" => Ok(PathBuf::from("/home/user/src")),
+                _ => Err(io::Error::new(io::ErrorKind::PermissionDenied, "deny")),
+            },
+            ..sys(read_detect_run_err, run_detect_ok)
+        };
+        let shell = Detector::new().detect_with(sys).unwrap();
+        assert_eq!(shell.cwd_with(sys).unwrap(), Path::new("/home/user/src"));
+        let shell = Shell::new("bash", Source::Passwd);
+        let err = shell.cwd_with(sys).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn version_scheme_per_shell() {
+        let cases = [
+            ("bash", "5.2.15", VersionScheme::SemverLike),
+            ("ksh", "2020.0.0", VersionScheme::SemverLike),
+            ("nu", "0.101.0", VersionScheme::SemverLike),
+            ("mksh", "R59", VersionScheme::MkshRelease),
+            ("ksh", "R59", VersionScheme::Raw),
+            ("ksh", "93u+", VersionScheme::Raw),
+            ("ksh", "88i", VersionScheme::Raw),
+            ("ksh", "2012-08-01", VersionScheme::DateBased),
+            ("rc", "20200101", VersionScheme::DateBased),
+        ];
+        for (name, version, scheme) in cases {
+            assert_eq!(VersionScheme::of(name, version), scheme, "{name} {version}");
+        }
+        let mut shell = Shell::new("mksh", Source::Process);
+        assert_eq!(shell.version_scheme(), None);
+        shell.version = Some("R59".to_string());
+        assert_eq!(shell.version_scheme(), Some(VersionScheme::MkshRelease));
+    }
+
+    #[test]
+    fn semver_normalized() {
+        let cases = [
+            ("5.2.15", Some("5.2.15"), Some(5)),
+            ("5.9", Some("5.9.0"), Some(5)),
+            ("0.21.0+archlinux1", Some("0.21.0"), Some(0)),
+            ("2020.0.0", Some("2020.0.0"), Some(2020)),
+            ("3.7.1-beta.2", Some("3.7.1"), Some(3)),
+            ("R59", Some("59.0.0"), Some(59)),
+            ("unknown", None, None),
+        ];
+        for (version, semver, major) in cases {
+            let mut shell = Shell::new("bash", Source::Process);
+            shell.version = Some(version.to_string());
+            assert_eq!(shell.semver().as_deref(), semver, "{version}");
+            assert_eq!(shell.major(), major, "{version}");
+        }
+        assert_eq!(Shell::new("dash", Source::Process).semver(), None);
+    }
+
+    #[test]
+    fn satisfies_requirements() {
+        let cases = [
+            ("5.2.15", ">=4.4", true),
+            ("4.3.48", ">=4.4", false),
+            ("5.2.15", "^5.1", true),
+            ("6.0.0", "^5.1", false),
+            ("0.21.0+archlinux1", "^0.21", true),
+            ("0.22.0", "^0.21", false),
+            ("5.9", "~5.9", true),
+            ("5.10", "~5.9", false),
+            ("5.2.15", "5", true),
+            ("5.2.15", "=5.2", true),
+            ("5.2.15", ">5.2", false),
+            ("5.2.15", "<=5.2", true),
+            ("5.2.15", ">=3.2, <6", true),
+            ("6.0", ">=3.2, <6", false),
+            ("R59", ">=58", true),
+            ("unknown", "*", true),
+            ("unknown", ">=0", false),
+        ];
+        for (version, requirement, expected) in cases {
+            let mut shell = Shell::new("bash", Source::Process);
+            shell.version = Some(version.to_string());
+            let satisfied = shell.satisfies(requirement).unwrap();
+            assert_eq!(satisfied, expected, "{version} {requirement}");
+        }
+        let shell = Shell::new("dash", Source::Process);
+        assert!(!shell.satisfies("^0.5").unwrap());
+        for requirement in [
+            "",
+            "bash",
+            ">=4.4.1.2",
+            "=>4",
+            "^5.",
+            ">=4,",
+            ">18446744073709551615",
+            "~1.18446744073709551615",
+        ] {
+            let err = shell.satisfies(requirement).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{requirement}");
+        }
+    }
+
+    #[test]
+    fn detect_with_owner() {
+        fn read_owner(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/proc/100/comm" => Ok("bash\n".to_string()),
+                "/proc/100/status" => Ok("PPid:\t1\nUid:\t0\t0\t0\t0\n".to_string()),
+                PASSWD => Ok("root:x:0:0:root:/root:/bin/bash\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        fn run_no_getent(name: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+            if name == GETENT {
+                return Err(io::Error::new(io::ErrorKind::NotFound, "no getent"));
+            }
+            run_detect_ok(name, args)
+        }
+
+        let sys = sys(read_owner, run_no_getent);
+        let shell = Detector::new().detect_with(sys).unwrap();
+        assert_eq!(shell.uid(), Some(0));
+        assert_eq!(shell.user_with(sys).unwrap(), "root");
+        let err = Shell::new("bash", Source::Environment)
+            .user_with(sys)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn detectors_are_independent() {
+        fn assert_shareable<T: Send + Sync>() {}
+
+        fn read_roots(path: &str) -> io::Result<String> {
+            match path {
+                "/a/self/status" | "/b/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/a/100/comm" => Ok("bash\n".to_string()),
+                "/b/100/comm" => Ok("zsh\n".to_string()),
+                _ => Err(io::Error::new(io::ErrorKind::NotFound, "gone")),
+            }
+        }
+
+        assert_shareable::<Detector>();
+        assert_shareable::<Shell>();
+        let sys = sys(read_roots, run_detect_ok);
+        let handles = ["/a", "/b"].map(|root| {
+            let detector = Detector::new().proc_root(root);
+            thread::spawn(move || detector.detect_with(sys).unwrap())
+        });
+        let names = handles.map(|handle| handle.join().unwrap().name().to_string());
+        assert_eq!(names, ["bash", "zsh"]);
+    }
+
+    #[test]
+    fn started_at_from_stat_and_uptime() {
+        fn read_times(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/proc/100/comm" => Ok("bash\n".to_string()),
+                "/proc/100/status" => Ok("PPid:\t1\n".to_string()),
+                "/proc/100/stat" => Ok(
+                    "100 (bash) S 1 100 100 34816 100 4194304 1 2 0 0 0 0 0 0 20 0 1 0 5000 1 1"
+                        .to_string(),
+                ),
+                "/proc/uptime" => Ok("350.25 700.10\n".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+
+        let sys = sys(read_times, run_detect_ok);
+        let shell = Detector::new().detect_with(sys).unwrap();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        let started = shell.started_at_with(sys, now).unwrap();
+        assert_eq!(
+            now.duration_since(started).unwrap(),
+            Duration::from_millis(300_250)
+        );
+    }
+
+    #[test]
+    fn session_leader_from_stat() {
+        fn read_session(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/self/status" => Ok("PPid:\t100\n".to_string()),
+                "/proc/100/comm" => Ok("bash\n".to_string()),
+                "/proc/100/status" => Ok("PPid:\t1\n".to_string()),
+                "/proc/100/stat" => Ok("100 (bash) S 1 100 100 34816".to_string()),
+                _ => unreachable!("bad path"),
+            }
+        }
+        fn read_nested(path: &str) -> io::Result<String> {
+            match path {
+                "/proc/100/stat" => Ok("100 (bash) S 90 100 90 34816".to_string()),
+                path => read_session(path),
+            }
+        }
+
+        let leader = sys(read_session, run_detect_ok);
+        let shell = Detector::new().detect_with(leader).unwrap();
+        assert!(shell.is_session_leader_with(leader).unwrap());
+        let nested = sys(read_nested, run_detect_ok);
+        assert!(!shell.is_session_leader_with(nested).unwrap());
+        let err = Shell::new("bash", Source::Environment)
+            .is_session_leader_with(leader)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn is_login_from_args() {
+        let cases: [(&[&str], bool); 5] = [
+            (&["-bash"], true),
+            (&["zsh", "-l"], true),
+            (&["bash", "--login", "-i"], true),
+            (&["bash", "-c", "ls -l"], false),
+            (&[], false),
+        ];
+        for (args, login) in cases {
+            let mut shell = Shell::new("bash", Source::Process);
+            shell.args = args.iter().map(ToString::to_string).collect();
+            assert_eq!(shell.is_login(), login, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn is_interactive_from_flags_and_tty() {
+        let cases: [(&[&str], bool, bool); 13] = [
+            (&["-bash"], true, true),
+            (&["-bash"], false, false),
+            (&["bash", "-i"], false, true),
+            (&["zsh", "-li"], false, true),
+            (&["bash", "-c", "make"], true, false),
+            (&["sh", "script.sh"], true, false),
+            (&["bash", "-o", "posix"], true, true),
+            (&["bash", "--", "-i"], true, false),
+            (&["pwsh", "-NoProfile"], true, true),
+            (&["pwsh", "-NonInteractive"], true, false),
+            (&["pwsh", "-noprofile"], false, false),
+            (&["elvish", "-norc"], true, true),
+            (&["fish", "-ic", "true"], true, false),
+        ];
+        for (args, tty, interactive) in cases {
+            let mut shell = Shell::new(args[0].trim_start_matches('-'), Source::Process);
+            shell.args = args.iter().map(ToString::to_string).collect();
+            shell.tty = tty.then(|| PathBuf::from("/dev/pts/3"));
+            assert_eq!(shell.is_interactive(), interactive, "{args:?} tty={tty}");
+        }
+        assert!(!Shell::new("bash", Source::Environment).is_interactive());
+    }
+
+    #[test]
+    fn mode_from_flags() {
+        let cases: [(&[&str], &str); 6] = [
+            (&["-bash"], "login"),
+            (&["bash", "-ic", "make"], "command"),
+            (&["bash", "script.sh", "-c"], "default"),
+            (&["fish", "--command", "true"], "command"),
+            (&["elvish", "-norc"], "interactive"),
+            (&["pwsh", "-noprofile"], "default"),
+        ];
+        for (args, mode) in cases {
+            let mut shell = Shell::new(args[0].trim_start_matches('-'), Source::Process);
+            shell.args = args.iter().map(ToString::to_string).collect();
+            shell.tty = (args[0] == "elvish").then(|| PathBuf::from("/dev/pts/3"));
+            assert_eq!(shell.mode(), Some(mode), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn detect_with_tty() {
+        let sys = Sys {
+            read_link: |path| match path {
+                "/proc/100/fd/0" => Ok(PathBuf::from("/dev/pts/7")),
+                _ => Err(io::Error::new(io::ErrorKind::PermissionDenied, "deny")),
+            },
+            ..sys(read_detect_run_err, run_detect_ok)
+        };
+        let shell = Detector::new().detect_with(sys).unwrap();
+        assert_eq!(shell.tty(), Some(Path::new("/dev/pts/7")));
+        let null = Sys {
+            read_link: |_| Ok(PathBuf::from("/dev/null")),
+            ..sys
+        };
+        assert_eq!(Detector::new().detect_with(null).unwrap().tty(), None);
+    }
 }
@@ -1,10 +1,1024 @@
 #![forbid(unsafe_code)]
-use shellver::Shell;
+mod i18n;
 
-fn main() -> std::io::Result<()> {
-    let shell = Shell::detect()?;
-    let name = shell.name();
-    let version = shell.version().unwrap_or_default();
-    println!("{name} {version}");
+use i18n::{Msg, en_arg, tr, tr_arg, tr_warning};
+use shellver::cache::MemoryStore;
+use shellver::capability::{self, Capability};
+use shellver::record::ErrorRecord;
+use shellver::render::{Env, Format, Long, Renderer, Template};
+use shellver::snapshot;
+use shellver::{
+    Activation, ChainEnd, Detector, Family, LogLevel, Shell, ShellNotFound, Source, Strategy,
+    VersionQuery,
+};
+use std::env;
+use std::error::Error;
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::hash::{BuildHasher, Hasher, RandomState};
+use std::io::{self, IsTerminal, Write};
+#[cfg(unix)]
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Default)]
+struct Args {
+    command: Option<String>,
+    proc_root: Option<PathBuf>,
+    /// A captured `/proc` to analyze offline, `--snapshot` or from `--ps`.
+    snapshot: Option<PathBuf>,
+    /// Where to read `ps` output from, `-` for stdin.
+    ps: Option<String>,
+    /// Where the walk of `--ps` output starts.
+    start_pid: Option<u32>,
+    output: Option<String>,
+    template: Option<String>,
+    shell: Option<String>,
+    default_shell: Option<String>,
+    version_query: VersionQuery,
+    timeout: Option<Duration>,
+    /// -1 for `-q`, 1 for `-v` and 2 for `-vv`.
+    verbosity: i8,
+    /// The poll interval of `--watch`.
+    watch: Option<Duration>,
+    /// Keeps versions across the polls of `--watch`.
+    cache: Option<Arc<MemoryStore>>,
+    all: bool,
+    all_versions: bool,
+    help: bool,
+    fallback: Fallback,
+    color: Color,
+    operands: Vec<String>,
+}
+
+/// When to color human output, `--color`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Color {
+    /// When stdout is a terminal and `NO_COLOR` is unset or empty.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl Color {
+    fn enabled(self) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => {
+                let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                !no_color && io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// When to fall back to `$SHELL` if the process walk fails.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Fallback {
+    /// Only when no shell was found among the parent processes.
+    #[default]
+    NotFound,
+    /// On any error, `--env-fallback`.
+    Always,
+    /// Never, `--strict`, which also fails without a version.
+    Never,
+}
+
+/// The subcommands, for `completions`.
+const COMMANDS: [&str; 13] = [
+    "ancestors",
+    "compare",
+    "completions",
+    "detect",
+    "doctor",
+    "dump",
+    "env",
+    "info",
+    "list",
+    "require",
+    "selfcheck",
+    "survey",
+    "which",
+];
+/// The options as short and long name, for `completions`.
+const OPTIONS: [(Option<char>, &str); 26] = [
+    (Some('a'), "all"),
+    (None, "all-versions"),
+    (None, "color"),
+    (None, "env-fallback"),
+    (None, "fallback"),
+    (None, "fingerprint"),
+    (None, "format"),
+    (Some('h'), "help"),
+    (None, "json"),
+    (Some('l'), "long"),
+    (None, "major"),
+    (None, "no-version"),
+    (Some('o'), "output"),
+    (None, "porcelain"),
+    (None, "pid"),
+    (None, "proc-root"),
+    (None, "ps"),
+    (Some('q'), "quiet"),
+    (None, "semver"),
+    (None, "shell"),
+    (None, "snapshot"),
+    (None, "strict"),
+    (None, "template"),
+    (None, "timeout"),
+    (Some('v'), "verbose"),
+    (None, "watch"),
+];
+
+/// How often `--watch` detects again.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+/// How long `--watch` trusts a version it looked up.
+const WATCH_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Exit status when a shell was found but its version was not.
+const EXIT_NO_VERSION: u8 = 2;
+/// Exit status when no shell was found, or a named one is not installed.
+const EXIT_NOT_FOUND: u8 = 3;
+/// Exit status when the process chain could not be read.
+const EXIT_PROC: u8 = 4;
+
+/// An error reading the process chain, as opposed to a walk that read
+/// everything and found no shell.
+#[derive(Debug)]
+struct ProcError(io::Error);
+
+impl fmt::Display for ProcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Error for ProcError {}
+
+/// A message of the CLI itself with its argument, printed translated and
+/// kept in English in JSON error records.
+#[derive(Debug)]
+struct Translated(Msg, String);
+
+impl fmt::Display for Translated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&tr_arg(self.0, &self.1))
+    }
+}
+
+impl Error for Translated {}
+
+/// `--strict` failing on a shell whose version is unknown.
+#[derive(Debug)]
+struct NoVersion(String);
+
+impl fmt::Display for NoVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&tr_arg(Msg::NoVersion, &self.0))
+    }
+}
+
+impl Error for NoVersion {}
+
+fn main() -> ExitCode {
+    let words: Vec<String> = env::args().skip(1).collect();
+    let args = match parse_args(words.iter().cloned()) {
+        Ok(args) => args,
+        Err(err) => return report(&err, wants_json(&words)),
+    };
+    let json = args.output.as_deref() == Some("json");
+    match run(args) {
+        Ok(code) => code,
+        // A closed pipe, e.g. `shellver survey | head -1`, means the reader
+        // has seen enough.
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(err) => report(&err, json),
+    }
+}
+
+// Prints `err` to stderr, as an `ErrorRecord` object under `--json` so
+// wrapping tools can branch on its kind, with the message in English.
+fn report(err: &io::Error, json: bool) -> ExitCode {
+    if json {
+        let mut record = ErrorRecord::from(err);
+        match err.get_ref() {
+            Some(inner) if inner.is::<ProcError>() => record.kind = "proc".to_string(),
+            Some(inner) if inner.is::<NoVersion>() => record.kind = "no_version".to_string(),
+            _ => {}
+        }
+        let inner = err.get_ref();
+        if let Some(Translated(msg, arg)) = inner.and_then(|inner| inner.downcast_ref()) {
+            record.message = en_arg(*msg, arg);
+        }
+        if let Some(NoVersion(name)) = inner.and_then(|inner| inner.downcast_ref()) {
+            record.message = en_arg(Msg::NoVersion, name);
+        }
+        eprintln!("{}", record.to_json());
+    } else {
+        eprintln!("{}: {err}", tr(Msg::Error));
+    }
+    exit_code(err)
+}
+
+fn exit_code(err: &io::Error) -> ExitCode {
+    match err.get_ref() {
+        Some(inner) if inner.is::<ShellNotFound>() => ExitCode::from(EXIT_NOT_FOUND),
+        Some(inner) if inner.is::<ProcError>() => ExitCode::from(EXIT_PROC),
+        Some(inner) if inner.is::<NoVersion>() => ExitCode::from(EXIT_NO_VERSION),
+        _ if err.kind() == io::ErrorKind::NotFound => ExitCode::from(EXIT_NOT_FOUND),
+        _ => ExitCode::FAILURE,
+    }
+}
+
+// Marks an error of the process walk as a failure to read the chain, unless
+// it is the walk's own `ShellNotFound`.
+fn proc_error(err: io::Error) -> io::Error {
+    if matches!(err.get_ref(), Some(inner) if inner.is::<ShellNotFound>()) {
+        return err;
+    }
+    io::Error::new(err.kind(), ProcError(err))
+}
+
+fn run(mut args: Args) -> io::Result<ExitCode> {
+    if args.help {
+        print!("{}", tr(Msg::Usage));
+        return Ok(ExitCode::SUCCESS);
+    }
+    let takes_operands = matches!(
+        args.command.as_deref(),
+        Some("compare" | "completions" | "require" | "which")
+    );
+    if let Some(arg) = args.operands.first().filter(|_| !takes_operands) {
+        return Err(invalid_input(Msg::UnexpectedArgument, arg));
+    }
+    // `ps` output is analyzed as a `/proc` tree written for this run.
+    let ps_dir = args
+        .ps
+        .take()
+        .map(|source| ps_snapshot(&source, args.start_pid))
+        .transpose()?;
+    args.snapshot = ps_dir.clone().or(args.snapshot);
+    if args.snapshot.is_some() {
+        // A snapshot has no binaries to ask, a missing version is expected.
+        args.version_query = VersionQuery::Skip;
+    }
+    let result = match args.command.as_deref() {
+        // A snapshot never changes, so it is printed once.
+        None | Some("detect") if args.watch.is_some() && args.snapshot.is_none() => watch(args),
+        None | Some("detect") => detect(&args),
+        Some("ancestors") => ancestors(&args),
+        Some("compare") => compare(&args),
+        Some("completions") => completions(&args),
+        Some("doctor") => doctor(&args),
+        Some("dump") => dump(&args),
+        Some("env") => env_exports(&args),
+        Some("info") => info(&args),
+        Some("list") => list(&args),
+        Some("require") => require(&args),
+        Some("selfcheck") => selfcheck(),
+        Some("survey") => survey(&args),
+        Some("which") => which(&args),
+        Some(arg) => Err(invalid_input(Msg::UnknownCommand, arg)),
+    };
+    if let Some(dir) = ps_dir {
+        let _ = fs::remove_dir_all(dir);
+    }
+    result
+}
+
+// Writes the `ps` output read from `source` as a `/proc` tree in a temporary
+// directory.
+fn ps_snapshot(source: &str, start: Option<u32>) -> io::Result<PathBuf> {
+    let ps = if source == "-" {
+        io::read_to_string(io::stdin())?
+    } else {
+        fs::read_to_string(source)?
+    };
+    let dir = private_temp_dir("shellver-ps")?;
+    snapshot::from_ps(&ps, start, &dir).inspect_err(|_| {
+        let _ = fs::remove_dir_all(&dir);
+    })?;
+    Ok(dir)
+}
+
+// Creates a new directory only the user can enter. Its name is random and an
+// existing one is never reused, as anyone may plant one in a shared `/tmp`.
+fn private_temp_dir(prefix: &str) -> io::Result<PathBuf> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    builder.mode(0o700);
+    for _ in 0..8 {
+        let suffix = RandomState::new().build_hasher().finish();
+        let dir = env::temp_dir().join(format!("{prefix}-{suffix:016x}"));
+        match builder.create(&dir) {
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            res => return res.map(|()| dir),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "no unused temporary directory name",
+    ))
+}
+
+fn parse_args(mut iter: impl Iterator<Item = String>) -> io::Result<Args> {
+    let mut args = Args::default();
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            let value = inline.clone().or_else(|| iter.next());
+            value.ok_or_else(|| invalid_input(Msg::MissingValue, flag))
+        };
+        match flag {
+            "--proc-root" => args.proc_root = Some(PathBuf::from(value()?)),
+            "--snapshot" => args.snapshot = Some(PathBuf::from(value()?)),
+            "--ps" => args.ps = Some(value()?),
+            "--pid" => {
+                let pid = value()?;
+                let parsed = pid
+                    .parse()
+                    .map_err(|_| invalid_input(Msg::InvalidPid, &pid));
+                args.start_pid = Some(parsed?);
+            }
+            "-o" | "--output" => args.output = Some(value()?),
+            "--template" | "--format" => args.template = Some(value()?),
+            "--shell" => args.shell = Some(value()?),
+            "--fallback" => args.default_shell = Some(value()?),
+            "--no-version" => args.version_query = VersionQuery::Skip,
+            "--color" => {
+                args.color = match value()?.as_str() {
+                    "auto" => Color::Auto,
+                    "always" => Color::Always,
+                    "never" => Color::Never,
+                    other => return Err(invalid_input(Msg::UnknownColor, other)),
+                }
+            }
+            "-q" | "--quiet" => args.verbosity = -1,
+            "-v" | "--verbose" => args.verbosity = (args.verbosity.max(0) + 1).min(2),
+            "-vv" => args.verbosity = 2,
+            "--timeout" => args.timeout = Some(seconds(&value()?)?),
+            // The interval is optional, so it can only be given inline.
+            "--watch" => {
+                let interval = inline.as_deref().map(seconds).transpose()?;
+                args.watch = Some(interval.unwrap_or(WATCH_INTERVAL));
+            }
+            "-a" | "--all" => args.all = true,
+            "--all-versions" => args.all_versions = true,
+            "--env-fallback" => args.fallback = Fallback::Always,
+            "--strict" => args.fallback = Fallback::Never,
+            "-l" | "--long" => args.output = Some("long".to_string()),
+            "--json" => args.output = Some("json".to_string()),
+            // Scripts may pin the version of the line format.
+            "--porcelain" => match inline.as_deref() {
+                None | Some("v1") => args.output = Some("porcelain".to_string()),
+                Some(version) => {
+                    return Err(invalid_input(Msg::UnknownFormat, version));
+                }
+            },
+            "--semver" => args.template = Some("{semver}".to_string()),
+            "--major" => args.template = Some("{major}".to_string()),
+            "--fingerprint" => args.template = Some("{fingerprint}".to_string()),
+            "-h" | "--help" => args.help = true,
+            _ if flag.starts_with('-') => {
+                return Err(invalid_input(Msg::UnknownOption, flag));
+            }
+            _ if args.command.is_none() => args.command = Some(arg),
+            _ => args.operands.push(arg),
+        }
+    }
+    Ok(args)
+}
+
+// Whether the command line asks for JSON, read leniently so errors that stop
+// `parse_args` are reported in the requested format too.
+fn wants_json(words: &[String]) -> bool {
+    let mut json = false;
+    let mut words = words.iter().map(String::as_str);
+    while let Some(arg) = words.next() {
+        json = match arg {
+            "--json" | "--output=json" => true,
+            "-o" | "--output" => words.next() == Some("json"),
+            "-l" | "--long" | "--porcelain" => false,
+            _ if arg.starts_with("--output=") || arg.starts_with("--porcelain=") => false,
+            _ => continue,
+        };
+    }
+    json
+}
+
+fn seconds(value: &str) -> io::Result<Duration> {
+    let seconds = value.parse().ok().filter(|secs: &f64| *secs > 0.0);
+    let duration = seconds.and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+    duration.ok_or_else(|| invalid_input(Msg::InvalidTimeout, value))
+}
+
+fn invalid_input(msg: Msg, arg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        Translated(msg, arg.to_string()),
+    )
+}
+
+fn detector(args: &Args) -> Detector {
+    let detector = Detector::new()
+        .log_level_from_env()
+        .version_query(args.version_query);
+    let detector = match &args.cache {
+        Some(store) => detector.cache(store.clone(), WATCH_CACHE_TTL),
+        None => detector,
+    };
+    let detector = match args.verbosity {
+        ..0 => detector.log_level(LogLevel::Off),
+        0 => detector,
+        1 => detector.log_level(LogLevel::Info),
+        _ => detector.log_level(LogLevel::Debug),
+    };
+    let detector = match args.timeout {
+        Some(timeout) => detector.version_timeout(timeout).timeout(timeout),
+        None => detector,
+    };
+    // `SHELLVER_SHELL` wins, except over a snapshot of another system.
+    let mut strategies = vec![Strategy::EnvOverride, Strategy::ProcWalk];
+    if args.snapshot.is_some() {
+        strategies.remove(0);
+    }
+    if args.fallback == Fallback::Always {
+        strategies.push(Strategy::EnvShell);
+    }
+    let detector = detector.strategies(strategies);
+    match (&args.snapshot, &args.proc_root) {
+        (Some(root), _) => detector.snapshot(root),
+        (None, Some(root)) => detector.proc_root(root),
+        (None, None) => detector,
+    }
+}
+
+// The `/proc` the detector reads, for commands that read it themselves.
+fn proc_root(args: &Args) -> &Path {
+    let root = args.snapshot.as_deref().or(args.proc_root.as_deref());
+    root.unwrap_or_else(|| Path::new("/proc"))
+}
+
+fn renderer(args: &Args) -> io::Result<Box<dyn Renderer>> {
+    let template = || {
+        let layout = args.template.clone();
+        layout.ok_or_else(|| invalid_input(Msg::TemplateRequired, ""))
+    };
+    let output = match (&args.output, &args.template) {
+        (None, Some(_)) => "template",
+        (output, _) => output.as_deref().unwrap_or("plain"),
+    };
+    let format = match output {
+        "template" => Format::Template(template()?),
+        other => other
+            .parse()
+            .map_err(|_| invalid_input(Msg::UnknownFormat, other))?,
+    };
+    if format == Format::Plain && args.color.enabled() {
+        return Ok(Box::new(ColorPlain));
+    }
+    Ok(format.renderer())
+}
+
+/// The plain `name version` line with the name in bold cyan and the version
+/// in green.
+struct ColorPlain;
+
+impl Renderer for ColorPlain {
+    fn render(&self, shell: &Shell, out: &mut dyn Write) -> io::Result<()> {
+        let name = shell.name();
+        let version = shell.version().unwrap_or_default();
+        writeln!(out, "\x1b[1;36m{name}\x1b[0m \x1b[32m{version}\x1b[0m")
+    }
+}
+
+fn detect(args: &Args) -> io::Result<ExitCode> {
+    let renderer = renderer(args)?;
+    if args.all && args.shell.is_none() {
+        let shells = detector(args).detect_all().map_err(proc_error)?;
+        return render_all(args, renderer.as_ref(), &shells);
+    }
+    let shell = detect_shell(args)?;
+    // A bare `--fallback` default has no version to miss.
+    let no_version = shell.version().is_none()
+        && args.version_query != VersionQuery::Skip
+        && shell.source() != Source::Default;
+    // `--strict` takes a shell without a version for a failure.
+    if no_version && args.fallback == Fallback::Never {
+        let err = NoVersion(shell.name().to_string());
+        return Err(io::Error::other(err));
+    }
+    renderer.render(&shell, &mut io::stdout().lock())?;
+    print_warnings(args, &shell);
+    if no_version {
+        return Ok(ExitCode::from(EXIT_NO_VERSION));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+// Detects again every interval and prints the result whenever it differs
+// from the last, e.g. after `exec fish`. Ends with an error once no shell is
+// left, such as when the watched shell exits.
+fn watch(mut args: Args) -> io::Result<ExitCode> {
+    let interval = args.watch.unwrap_or(WATCH_INTERVAL);
+    args.cache = Some(Arc::default());
+    let renderer = renderer(&args)?;
+    let mut last = None;
+    loop {
+        let shell = detect_shell(&args)?;
+        let mut text = Vec::new();
+        renderer.render(&shell, &mut text)?;
+        if last.as_ref() != Some(&text) {
+            let mut out = io::stdout().lock();
+            out.write_all(&text)?;
+            out.flush()?;
+            print_warnings(&args, &shell);
+            last = Some(text);
+        }
+        thread::sleep(interval);
+    }
+}
+
+// Unless `--strict`, a walk that finds no shell, e.g. when started from a GUI
+// file manager, falls back to `$SHELL`, which is reported as a warning. An
+// unreadable `/proc` still fails. `--shell` skips detection for the named
+// shell as `which` finds it, and `--fallback` reports a default shell the
+// same way when detection fails.
+fn detect_shell(args: &Args) -> io::Result<Shell> {
+    if let Some(name) = &args.shell {
+        return installation(args, name);
+    }
+    let detected = walk_shell(args);
+    match (detected, &args.default_shell) {
+        // A default that is not installed is still reported, bare.
+        (Err(_), Some(name)) => Ok(installation(args, name).unwrap_or_else(|_| Shell::named(name))),
+        (detected, _) => detected,
+    }
+}
+
+fn walk_shell(args: &Args) -> io::Result<Shell> {
+    let err = match detector(args).detect() {
+        Ok(shell) => return Ok(shell),
+        Err(err) => err,
+    };
+    // A bad `SHELLVER_SHELL` is the user's error, not one of the chain.
+    if err.kind() == io::ErrorKind::InvalidInput {
+        return Err(err);
+    }
+    let not_found = matches!(err.get_ref(), Some(inner) if inner.is::<ShellNotFound>());
+    if args.fallback != Fallback::NotFound || !not_found {
+        return Err(proc_error(err));
+    }
+    let env = detector(args).strategies([Strategy::EnvShell]);
+    env.detect().map_err(|_| err)
+}
+
+// The long summary plus what is activated in the calling shell. Programs it
+// starts see its current environment, unlike `/proc/<pid>/environ`.
+fn info(args: &Args) -> io::Result<ExitCode> {
+    let shell = detect_shell(args)?;
+    let vars = env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)));
+    let activations = Activation::from_vars(vars);
+    let mut out = io::stdout().lock();
+    Long.render(&shell, &mut out)?;
+    for kind in ["venv", "conda", "direnv"] {
+        let active = activations
+            .iter()
+            .find(|activation| activation.kind() == kind);
+        let value = active.map_or_else(|| "-".to_string(), ToString::to_string);
+        writeln!(out, "{:<12}{value}", format!("{kind}:"))?;
+    }
+    print_warnings(args, &shell);
+    Ok(ExitCode::SUCCESS)
+}
+
+// Exits with failure, rather than an error, when the version is too old or
+// unknown, so installer scripts can branch on it. A named shell is checked
+// as found by `which`, otherwise the current one.
+fn require(args: &Args) -> io::Result<ExitCode> {
+    let (shell, requirement) = match args.operands.as_slice() {
+        [requirement] => (detect_shell(args)?, requirement),
+        [name, requirement] => (installation(args, name)?, requirement),
+        _ => return Err(invalid_input(Msg::RequireNeedsOne, "")),
+    };
+    if shell.satisfies(requirement)? {
+        return Ok(ExitCode::SUCCESS);
+    }
+    let version = shell.version().unwrap_or_else(|| "-".to_string());
+    let found = format!("{} {version}", shell.name());
+    eprintln!("{}", tr_arg(Msg::Unsatisfied, &found));
+    Ok(ExitCode::FAILURE)
+}
+
+fn env_exports(args: &Args) -> io::Result<ExitCode> {
+    let shell = detect_shell(args)?;
+    Env.render(&shell, &mut io::stdout().lock())?;
+    print_warnings(args, &shell);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn survey(args: &Args) -> io::Result<ExitCode> {
+    let renderer = renderer(args)?;
+    let shells = detector(args).survey().map_err(proc_error)?;
+    render_all(args, renderer.as_ref(), &shells)
+}
+
+fn render_all(args: &Args, renderer: &dyn Renderer, shells: &[Shell]) -> io::Result<ExitCode> {
+    renderer.render_all(shells, &mut io::stdout().lock())?;
+    for shell in shells {
+        print_warnings(args, shell);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_warnings(args: &Args, shell: &Shell) {
+    if args.verbosity < 0 {
+        return;
+    }
+    for warning in shell.warnings() {
+        let code = warning.code();
+        eprintln!("{}: [{code}] {}", tr(Msg::Warning), tr_warning(warning));
+    }
+}
+
+// With a requirement, e.g. `which fish '>=3.6'`, installations that do not
+// meet it are passed over as if they were not there.
+fn which(args: &Args) -> io::Result<ExitCode> {
+    let (name, requirement) = match args.operands.as_slice() {
+        [name] => (name, None),
+        [name, requirement] => (name, Some(requirement)),
+        _ => return Err(invalid_input(Msg::WhichNeedsOne, "")),
+    };
+    let renderer: Box<dyn Renderer> = match (&args.output, &args.template) {
+        (None, None) => Box::new(Template::new("{path} {version}")),
+        _ => renderer(args)?,
+    };
+    let mut shells = detector(args).installations(name)?;
+    if let Some(requirement) = requirement {
+        let mut satisfied = Vec::new();
+        for shell in shells {
+            if shell.satisfies(requirement)? {
+                satisfied.push(shell);
+            }
+        }
+        shells = satisfied;
+    }
+    if shells.is_empty() {
+        return Err(not_installed(name));
+    }
+    if !args.all_versions {
+        shells.truncate(1);
+    }
+    render_all(args, renderer.as_ref(), &shells)
+}
+
+fn installation(args: &Args, name: &str) -> io::Result<Shell> {
+    let shells = detector(args).installations(name)?;
+    shells.into_iter().next().ok_or_else(|| not_installed(name))
+}
+
+fn not_installed(name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        Translated(Msg::NotInstalled, name.to_string()),
+    )
+}
+
+fn compare(args: &Args) -> io::Result<ExitCode> {
+    let [left, right] = args.operands.as_slice() else {
+        return Err(invalid_input(Msg::CompareNeedsTwo, ""));
+    };
+    let detector = detector(args);
+    let version = |shell: &str| match detector.version_of(shell) {
+        Ok(version) => Ok(version.unwrap_or_else(|| "-".to_string())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Err(not_installed(shell)),
+        Err(err) => Err(err),
+    };
+    let supports = |shell: &str, capability| {
+        let name = Path::new(shell).file_name().and_then(|name| name.to_str());
+        let supported = capability::of(name.unwrap_or(shell)).contains(&capability);
+        tr(if supported { Msg::Yes } else { Msg::No }).to_string()
+    };
+    let mut rows = vec![
+        [String::new(), left.clone(), right.clone()],
+        ["version".to_string(), version(left)?, version(right)?],
+    ];
+    rows.extend(Capability::ALL.map(|capability| {
+        [
+            capability.as_str().to_string(),
+            supports(left, capability),
+            supports(right, capability),
+        ]
+    }));
+    let width = |column: usize| rows.iter().map(|row| row[column].chars().count()).max();
+    let widths = [width(0), width(1)].map(Option::unwrap_or_default);
+    let mut out = io::stdout().lock();
+    for [label, left, right] in &rows {
+        let line = format!("{label:<0$}  {left:<1$}  {right}", widths[0], widths[1]);
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+// Walks the parent chain like a plain `shellver` and explains what may keep
+// it from finding the shell, one finding per line in the style of
+// `selfcheck`. Fails if the shell or its version could not be found.
+fn doctor(args: &Args) -> io::Result<ExitCode> {
+    let mut out = io::stdout().lock();
+    let mut failed = false;
+    let proc_root = proc_root(args);
+    if let Some(option) = hidepid(proc_root) {
+        writeln!(out, "warn {}", tr_arg(Msg::DoctorHidepid, &option))?;
+    }
+    let detected = match detector(args).detect() {
+        Ok(shell) => {
+            let version = shell.version().unwrap_or_else(|| "-".to_string());
+            let pid = shell.pid().map(|pid| format!(" (pid {pid})"));
+            let found = format!("{} {version}{}", shell.name(), pid.unwrap_or_default());
+            writeln!(out, "ok   {}", tr_arg(Msg::DoctorDetected, &found))?;
+            if shell.version().is_none() {
+                failed = true;
+                writeln!(out, "FAIL {}", tr_arg(Msg::DoctorNoVersion, shell.name()))?;
+            }
+            Some(shell)
+        }
+        Err(err) => {
+            failed = true;
+            let not_found = err
+                .get_ref()
+                .and_then(|err| err.downcast_ref::<ShellNotFound>());
+            match not_found {
+                Some(not_found) => not_found_findings(not_found, &mut out)?,
+                None => writeln!(
+                    out,
+                    "FAIL {}",
+                    tr_arg(Msg::DoctorProcUnreadable, &err.to_string())
+                )?,
+            }
+            None
+        }
+    };
+    match env::var("SHELL") {
+        Err(_) => writeln!(out, "warn {}", tr(Msg::DoctorShellUnset))?,
+        Ok(var) => {
+            let name = Path::new(&var).file_name().and_then(|name| name.to_str());
+            let running = detected.as_ref().map(Shell::name);
+            if running.is_some_and(|running| Some(running) != name) {
+                writeln!(out, "warn {}", tr_arg(Msg::DoctorShellDiffers, &var))?;
+            }
+        }
+    }
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn not_found_findings(not_found: &ShellNotFound, out: &mut dyn Write) -> io::Result<()> {
+    match &not_found.end {
+        ChainEnd::HopLimit => {
+            let hops = not_found.chain.len().to_string();
+            writeln!(out, "FAIL {}", tr_arg(Msg::DoctorHopLimit, &hops))?;
+        }
+        ChainEnd::SessionManager(comm) => {
+            writeln!(out, "FAIL {}", tr_arg(Msg::DoctorOrphaned, comm))?;
+        }
+        _ => writeln!(out, "FAIL {}", tr_arg(Msg::DoctorOrphaned, "init"))?,
+    }
+    if not_found.nested_pid_namespace {
+        writeln!(out, "warn {}", tr(Msg::DoctorNamespace))?;
+    }
     Ok(())
 }
+
+// The `hidepid` mount option of the procfs at `root`, if it hides anything.
+fn hidepid(root: &Path) -> Option<String> {
+    let mounts = fs::read_to_string("/proc/self/mounts").ok()?;
+    let options = mounts.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(' ').collect();
+        let proc_mount = fields.get(2) == Some(&"proc");
+        (proc_mount && fields.get(1).map(Path::new) == Some(root))
+            .then(|| fields.get(3).copied())?
+    })?;
+    options
+        .split(',')
+        .find(|option| {
+            option.starts_with("hidepid=") && !matches!(*option, "hidepid=0" | "hidepid=off")
+        })
+        .map(str::to_string)
+}
+
+// The parent chain as the walk sees it, with the shell each hop was taken
+// for, so users can report why detection picked or missed a shell.
+fn ancestors(args: &Args) -> io::Result<ExitCode> {
+    let ancestors = detector(args).ancestors().map_err(proc_error)?;
+    let mut rows = vec![["PID", "COMM", "SHELL"].map(str::to_string)];
+    rows.extend(ancestors.iter().map(|hop| {
+        [
+            hop.pid().to_string(),
+            hop.comm().to_string(),
+            hop.shell().unwrap_or("-").to_string(),
+        ]
+    }));
+    let width = |column: usize| rows.iter().map(|row| row[column].chars().count()).max();
+    let widths = [width(0), width(1)].map(Option::unwrap_or_default);
+    let mut out = io::stdout().lock();
+    for [pid, comm, shell] in &rows {
+        writeln!(out, "{pid:<0$}  {comm:<1$}  {shell}", widths[0], widths[1])?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+// Lines of `/proc/<pid>/status` kept in a dump, the rest may identify the
+// machine without helping to debug detection.
+const DUMP_STATUS_KEYS: [&str; 7] = ["Name", "State", "Pid", "PPid", "Uid", "Gid", "NSpid"];
+
+// A support bundle for bug reports: the chain's `/proc` files, `$SHELL` and
+// the version output, with the home directory and argument values redacted.
+fn dump(args: &Args) -> io::Result<ExitCode> {
+    let home = env::var("HOME").ok().filter(|home| home.len() > 1);
+    let redact = |text: &str| {
+        home.as_ref()
+            .map_or_else(|| text.to_string(), |home| text.replace(home.as_str(), "~"))
+    };
+    let proc_root = proc_root(args);
+    let mut out = io::stdout().lock();
+    writeln!(out, "shellver {}", env!("CARGO_PKG_VERSION"))?;
+    let shell_var = env::var("SHELL").unwrap_or_else(|_| "-".to_string());
+    writeln!(out, "SHELL: {}", redact(&shell_var))?;
+    match detector(args).detect() {
+        Ok(shell) => {
+            let version = shell.version().unwrap_or_else(|| "-".to_string());
+            writeln!(out, "detected: {} {version}", shell.name())?;
+            let command = Shell::version_command(shell.name());
+            writeln!(out, "command: {}", command.as_deref().unwrap_or("-"))?;
+            writeln!(out, "output:")?;
+            for line in shell.version_raw().unwrap_or_default().lines() {
+                writeln!(out, "{}", format!("  {}", redact(line)).trim_end())?;
+            }
+        }
+        Err(err) => writeln!(out, "detected: {}", redact(&err.to_string()))?,
+    }
+    for hop in detector(args).ancestors().map_err(proc_error)? {
+        let dir = proc_root.join(hop.pid().to_string());
+        writeln!(out, "\n{}:", dir.display())?;
+        writeln!(out, "  comm: {}", hop.comm())?;
+        match fs::read_to_string(dir.join("status")) {
+            Ok(status) => {
+                let kept = status.lines().filter(|line| {
+                    line.split_once(':')
+                        .is_some_and(|(key, _)| DUMP_STATUS_KEYS.contains(&key))
+                });
+                for line in kept {
+                    writeln!(out, "  status: {}", line.replace('\t', " "))?;
+                }
+            }
+            Err(err) => writeln!(out, "  status: {err}")?,
+        }
+        match fs::read(dir.join("cmdline")) {
+            Ok(cmdline) => writeln!(out, "  cmdline: {}", redact(&redact_cmdline(&cmdline)))?,
+            Err(err) => writeln!(out, "  cmdline: {err}")?,
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+// Keeps the program and option names of a NUL separated command line and
+// replaces every value, which may hold paths, hosts or tokens.
+fn redact_cmdline(cmdline: &[u8]) -> String {
+    let cmdline = String::from_utf8_lossy(cmdline);
+    let mut words = cmdline.split_terminator('\0');
+    let program = words.next().unwrap_or_default().to_string();
+    let words = words.map(|word| match word.split_once('=') {
+        Some((flag, _)) if flag.starts_with('-') => format!("{flag}=<redacted>"),
+        _ if word.starts_with('-') => word.to_string(),
+        _ => "<redacted>".to_string(),
+    });
+    std::iter::once(program)
+        .chain(words)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Completion scripts for the CLI itself, generated from `COMMANDS` and
+// `OPTIONS` so they follow new flags.
+fn completions(args: &Args) -> io::Result<ExitCode> {
+    let words = || {
+        let options = OPTIONS.iter().map(|(_, long)| format!("--{long}"));
+        COMMANDS
+            .map(String::from)
+            .into_iter()
+            .chain(options)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let script = match args.operands.as_slice() {
+        [shell] if shell == "bash" => format!(
+            "_shellver() {{\n    \
+                 COMPREPLY=($(compgen -W \"{}\" -- \"${{COMP_WORDS[COMP_CWORD]}}\"))\n\
+             }}\n\
+             complete -F _shellver shellver\n",
+            words()
+        ),
+        [shell] if shell == "zsh" => format!(
+            "#compdef shellver\n\
+             _shellver() {{\n    \
+                 compadd -- {}\n\
+             }}\n\
+             compdef _shellver shellver\n",
+            words()
+        ),
+        [shell] if shell == "fish" => {
+            let mut script = format!(
+                "complete -c shellver -f -n __fish_use_subcommand -a '{}'\n",
+                COMMANDS.join(" ")
+            );
+            for (short, long) in OPTIONS {
+                let short = short
+                    .map(|short| format!(" -s {short}"))
+                    .unwrap_or_default();
+                let _ = writeln!(script, "complete -c shellver{short} -l {long}");
+            }
+            script
+        }
+        _ => return Err(invalid_input(Msg::CompletionsNeedsShell, "")),
+    };
+    io::stdout().lock().write_all(script.as_bytes())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+// Every supported shell with how its version is asked for and, when
+// installed, the first installation, to explain why a shell goes undetected.
+fn list(args: &Args) -> io::Result<ExitCode> {
+    let detector = detector(args);
+    let mut rows = vec![["NAME", "FAMILY", "COMMAND", "INSTALLED"].map(str::to_string)];
+    for &name in Shell::supported_shells() {
+        let installed = detector.installations(name)?.into_iter().next();
+        let installed = installed.map_or_else(
+            || "-".to_string(),
+            |shell| {
+                let path = shell.path().map(|path| path.display().to_string());
+                let version = shell.version().unwrap_or_else(|| "-".to_string());
+                format!("{} {version}", path.unwrap_or_default())
+            },
+        );
+        rows.push([
+            name.to_string(),
+            Family::of(name).as_str().to_string(),
+            Shell::version_command(name).unwrap_or_else(|| "-".to_string()),
+            installed,
+        ]);
+    }
+    let width = |column: usize| rows.iter().map(|row| row[column].chars().count()).max();
+    let widths = [width(0), width(1), width(2)].map(Option::unwrap_or_default);
+    let mut out = io::stdout().lock();
+    for [name, family, command, installed] in &rows {
+        let line = format!(
+            "{name:<0$}  {family:<1$}  {command:<2$}  {installed}",
+            widths[0], widths[1], widths[2]
+        );
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn selfcheck() -> io::Result<ExitCode> {
+    let mut out = io::stdout().lock();
+    let mut failed = false;
+    for check in Shell::self_check() {
+        let name = check.name();
+        if let Some(version) = check.version() {
+            writeln!(out, "ok   {name} {version}")?;
+        } else {
+            failed = true;
+            writeln!(out, "FAIL {}", tr_arg(Msg::Unparsed, name))?;
+            if check.raw().trim().is_empty() {
+                writeln!(out, "     | {}", tr(Msg::NoOutput))?;
+            }
+            for line in check.raw().lines() {
+                writeln!(out, "     | {line}")?;
+            }
+        }
+    }
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+#[cfg(test)]
+mod i18n_tests;
+#[cfg(test)]
+mod main_tests;
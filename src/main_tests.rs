@@ -0,0 +1,74 @@
+mod tests {
+    use crate::i18n::{Lang, Msg, text, tr_arg};
+    use crate::{COMMANDS, OPTIONS, parse_args};
+
+    // The flags matched in `parse_args`, read from its source.
+    fn parsed_flags() -> Vec<&'static str> {
+        let source = include_str!("main.rs");
+        let start = source.find("fn parse_args(").unwrap();
+        let end = start + source[start..].find("\n}\n").unwrap();
+        source[start..end]
+            .split('"')
+            .skip(1)
+            .step_by(2)
+            .filter(|literal| {
+                literal.starts_with('-') && !literal.trim_start_matches('-').is_empty()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn options_list_every_parsed_flag() {
+        let flags = parsed_flags();
+        assert!(flags.contains(&"--output"), "{flags:?}");
+        for flag in flags {
+            let listed = OPTIONS.iter().any(|&(short, long)| {
+                flag.strip_prefix("--").map_or_else(
+                    // `-vv` is `-v` given twice.
+                    || flag[1..].chars().all(|ch| short == Some(ch)),
+                    |name| name == long,
+                )
+            });
+            assert!(listed, "{flag} missing from OPTIONS");
+        }
+    }
+
+    #[test]
+    fn parse_args_accepts_every_option() {
+        let flags = OPTIONS.iter().flat_map(|&(short, long)| {
+            let short = short.map(|ch| format!("-{ch}"));
+            short.into_iter().chain([format!("--{long}")])
+        });
+        for flag in flags {
+            // The value is taken only by options that need one, and may
+            // be rejected, but the flag itself must be known.
+            let args = [flag.clone(), "1".to_string()];
+            if let Err(err) = parse_args(args.into_iter()) {
+                assert_ne!(err.to_string(), tr_arg(Msg::UnknownOption, &flag));
+            }
+        }
+    }
+
+    #[test]
+    fn usage_lists_every_command_and_option() {
+        for lang in [Lang::En, Lang::De, Lang::Hu] {
+            let usage = text(lang, Msg::Usage);
+            // Option names end at their value or optional `[=...]` part.
+            let words: Vec<&str> = usage
+                .split_whitespace()
+                .map(|word| word.split('[').next().unwrap_or_default())
+                .collect();
+            for command in COMMANDS {
+                assert!(words.contains(&command), "{lang:?} lacks {command}");
+            }
+            for (short, long) in OPTIONS {
+                let line = short.map_or_else(
+                    || format!("      --{long}"),
+                    |short| format!("  -{short}, --{long}"),
+                );
+                let listed = words.contains(&format!("--{long}").as_str());
+                assert!(listed && usage.contains(&line), "{lang:?} lacks --{long}");
+            }
+        }
+    }
+}
@@ -0,0 +1,309 @@
+//! Pure parsers for the `/proc` files and version banners read during
+//! detection.
+//!
+//! Every parser takes raw bytes, never panics and looks at no more than a
+//! bounded prefix of its input, so they can be fuzzed directly and reused on
+//! captured files:
+//!
+//! ```
+//! use shellver::parse;
+//!
+//! let stat = b"4242 (bash) S 1 4242 4242 0";
+//! assert_eq!(parse::stat_comm(stat)?, "bash");
+//! assert_eq!(parse::stat_ppid(stat)?, 1);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+
+/// Bytes looked at in `status`, `stat`, `uptime` and version banners. The
+/// real files are a few KiB at most.
+pub const MAX_FILE: usize = 64 * 1024;
+
+/// Bytes looked at in `cmdline` and `environ`, which are bounded by the
+/// kernel's argument size limit.
+pub const MAX_ARGS: usize = 2 * 1024 * 1024;
+
+/// Bytes of a shell binary scanned for an embedded version, more than the
+/// size of any shell without a version command.
+pub const MAX_BINARY: usize = 8 * 1024 * 1024;
+
+// Printable runs shorter than this are noise, as with `strings`.
+const MIN_STRING: usize = 4;
+
+const SEMVER_PATTERN: &str = r"[0-9]+\.[0-9]+(?:\.[0-9]+)?";
+const MKSH_PATTERN: &str = r"R[0-9]+";
+// `BusyBox v1.36.1 (2023-11-07 18:53:09 UTC) multi-call binary.`, where the
+// build date must not be mistaken for the version.
+const BUSYBOX_PATTERN: &str = r"BusyBox v([0-9]+\.[0-9]+(?:\.[0-9]+)?)";
+// Undotted ksh releases: ksh88's `Version M-11/16/88i`, where the date must not
+// be mistaken for the version, and ksh93's `(AT&T Research) 93u+ 2012-08-01`
+// from `--version` or `Version AJM 93u+ 2012-08-01` from `$KSH_VERSION`.
+// Newer ksh93u+m builds carry a dotted version, which is preferred.
+const KSH_PATTERN: &str = concat!(
+    r"Version (?:[A-Z]+-)?[0-9]{2}/[0-9]{2}/([0-9]{2}[a-z]?)",
+    r"|\(AT&T Research\) ([0-9]{2}[a-z]?\+?)",
+    r"|Version [A-Z]+ ([0-9]{2}[a-z]?\+?)(?: |$)",
+);
+// `ion 1.0.0-alpha (x86_64-unknown-redox)`, keeping the pre-release.
+const ION_PATTERN: &str = r"ion ([0-9]+\.[0-9]+(?:\.[0-9]+)?(?:-[0-9A-Za-z.]+)?)";
+// `Oils 0.22.0` or `Oil version 0.9.0` on the first of several lines, which
+// go on with the versions of the build's compiler and interpreter.
+const OILS_PATTERN: &str = r"(?m)^Oils?(?: version)? ([0-9]+\.[0-9]+(?:\.[0-9]+)?)";
+
+fn text(bytes: &[u8], limit: usize) -> Cow<'_, str> {
+    String::from_utf8_lossy(&bytes[..bytes.len().min(limit)])
+}
+
+// Line based input with CRLF line endings, as in fixtures or command output
+// made on Windows, is read like LF so every parser behaves the same.
+fn line_text(bytes: &[u8], limit: usize) -> Cow<'_, str> {
+    let text = text(bytes, limit);
+    if text.contains('\r') {
+        Cow::Owned(text.replace("\r\n", "\n"))
+    } else {
+        text
+    }
+}
+
+/// Returns the parent pid from the `PPid:` line of `/proc/<pid>/status`.
+///
+/// # Errors
+///
+/// Returns `NotFound` if the line is missing and `InvalidData` if its value
+/// is not a pid.
+pub fn status_ppid(status: &[u8]) -> io::Result<u32> {
+    let text = line_text(status, MAX_FILE);
+    let ppid = text.lines().find_map(|line| line.strip_prefix("PPid:"));
+    let ppid = ppid.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "PPid not found"))?;
+    ppid.trim()
+        .parse::<u32>()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "PPid parse failed"))
+}
+
+/// Returns the real user id from the `Uid:` line of `/proc/<pid>/status`.
+///
+/// # Errors
+///
+/// Returns `NotFound` if the line is missing and `InvalidData` if its first
+/// value is not a uid.
+pub fn status_uid(status: &[u8]) -> io::Result<u32> {
+    let text = line_text(status, MAX_FILE);
+    let uid = text.lines().find_map(|line| line.strip_prefix("Uid:"));
+    let uid = uid.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Uid not found"))?;
+    uid.split_whitespace()
+        .next()
+        .and_then(|uid| uid.parse::<u32>().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Uid parse failed"))
+}
+
+/// Returns the pids from the `NSpid:` line of `/proc/<pid>/status`.
+///
+/// They run from the outermost PID namespace the process is visible in to its
+/// own, so more than one pid means it runs in a nested namespace, e.g. a
+/// container.
+///
+/// # Errors
+///
+/// Returns `NotFound` if the line is missing, as before Linux 4.1, and
+/// `InvalidData` if a value is not a pid.
+pub fn status_nspid(status: &[u8]) -> io::Result<Vec<u32>> {
+    let text = line_text(status, MAX_FILE);
+    let pids = text.lines().find_map(|line| line.strip_prefix("NSpid:"));
+    let pids = pids.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "NSpid not found"))?;
+    pids.split_whitespace()
+        .map(|pid| {
+            pid.parse::<u32>()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "NSpid parse failed"))
+        })
+        .collect()
+}
+
+// The `comm` field is wrapped in parentheses and may itself contain spaces or
+// parentheses, so split on the last closing parenthesis.
+fn split_stat(text: &str) -> io::Result<(&str, &str)> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "stat parse failed");
+    let start = text.find('(').ok_or_else(invalid)?;
+    let end = text.rfind(')').ok_or_else(invalid)?;
+    if end < start {
+        return Err(invalid());
+    }
+    Ok((&text[start + 1..end], &text[end + 1..]))
+}
+
+// Returns field `n` of stat, counting the state after `comm` as field 3.
+fn stat_field(stat: &[u8], n: usize, what: &str) -> io::Result<u64> {
+    let text = line_text(stat, MAX_FILE);
+    let (_, rest) = split_stat(&text)?;
+    rest.split_whitespace()
+        .nth(n - 3)
+        .and_then(|field| field.parse::<u64>().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("{what} parse failed")))
+}
+
+/// Returns the command name from `/proc/<pid>/stat`.
+///
+/// # Errors
+///
+/// Returns `InvalidData` if the name is not enclosed in parentheses.
+pub fn stat_comm(stat: &[u8]) -> io::Result<String> {
+    let text = line_text(stat, MAX_FILE);
+    let (comm, _) = split_stat(&text)?;
+    Ok(comm.to_string())
+}
+
+/// Returns the parent pid, field 4 of `/proc/<pid>/stat`.
+///
+/// # Errors
+///
+/// Returns `InvalidData` if the line is malformed.
+pub fn stat_ppid(stat: &[u8]) -> io::Result<u32> {
+    let ppid = stat_field(stat, 4, "PPid")?;
+    u32::try_from(ppid).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "PPid parse failed"))
+}
+
+/// Returns the session id, field 6 of `/proc/<pid>/stat`.
+///
+/// # Errors
+///
+/// Returns `InvalidData` if the line is malformed.
+pub fn stat_session(stat: &[u8]) -> io::Result<u32> {
+    let session = stat_field(stat, 6, "session")?;
+    u32::try_from(session)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "session parse failed"))
+}
+
+/// Returns the start time in clock ticks after boot, field 22 of
+/// `/proc/<pid>/stat`.
+///
+/// # Errors
+///
+/// Returns `InvalidData` if the line is malformed.
+pub fn stat_start_ticks(stat: &[u8]) -> io::Result<u64> {
+    stat_field(stat, 22, "starttime")
+}
+
+/// Returns the address of the argument block, field 48 of
+/// `/proc/<pid>/stat`. It reads as 0 unless the caller may trace the process.
+///
+/// # Errors
+///
+/// Returns `InvalidData` if the line is malformed or predates Linux 3.5.
+pub fn stat_arg_start(stat: &[u8]) -> io::Result<u64> {
+    stat_field(stat, 48, "arg_start")
+}
+
+/// Returns the system uptime from `/proc/uptime`.
+///
+/// # Errors
+///
+/// Returns `InvalidData` unless the first value is a finite, non-negative
+/// number of seconds.
+pub fn uptime(uptime: &[u8]) -> io::Result<Duration> {
+    let text = line_text(uptime, MAX_FILE);
+    text.split_whitespace()
+        .next()
+        .and_then(|seconds| seconds.parse::<f64>().ok())
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "uptime parse failed"))
+}
+
+/// Splits `/proc/<pid>/cmdline` into its arguments, `argv[0]` included.
+/// Arguments are NUL terminated and need not be UTF-8.
+#[must_use]
+pub fn cmdline(cmdline: &[u8]) -> Vec<String> {
+    let bytes = &cmdline[..cmdline.len().min(MAX_ARGS)];
+    let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+    if bytes.is_empty() {
+        return Vec::new();
+    }
+    bytes
+        .split(|&byte| byte == 0)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+/// Parses `/proc/<pid>/environ` into a map, skipping entries without `=`.
+#[must_use]
+pub fn environ(environ: &[u8]) -> HashMap<String, String> {
+    let text = text(environ, MAX_ARGS);
+    text.split('\0')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Returns the value of `var` from `/proc/<pid>/environ`.
+#[must_use]
+pub fn environ_var(environ: &[u8], var: &str) -> Option<String> {
+    let text = text(environ, MAX_ARGS);
+    text.split('\0')
+        .find_map(|entry| entry.strip_prefix(var)?.strip_prefix('='))
+        .map(str::to_string)
+}
+
+/// Extracts the version of `shell` from the output of its version command,
+/// e.g. `5.2.15` from `GNU bash, version 5.2.15(1)-release`.
+#[must_use]
+pub fn version(shell: &str, banner: &[u8]) -> Option<String> {
+    let patterns: &[&str] = match shell {
+        "mksh" => &[MKSH_PATTERN],
+        "busybox" => &[BUSYBOX_PATTERN],
+        "osh" | "ysh" => &[OILS_PATTERN],
+        "ion" => &[ION_PATTERN],
+        "ksh" => &[SEMVER_PATTERN, KSH_PATTERN],
+        _ => &[SEMVER_PATTERN],
+    };
+    let text = line_text(banner, MAX_FILE);
+    patterns.iter().find_map(|pattern| {
+        let caps = Regex::new(pattern).ok()?.captures(&text)?;
+        // The first group that took part, or the whole match without groups.
+        let found = caps
+            .iter()
+            .skip(1)
+            .flatten()
+            .next()
+            .or_else(|| caps.get(0))?;
+        Some(found.as_str().to_string())
+    })
+}
+
+/// Returns what directly follows the version in a version banner.
+///
+/// This is the pre-release or build metadata distro builds carry, e.g.
+/// `(1)-release` from `GNU bash, version 5.2.15(1)-release` or `+archlinux1`
+/// from `0.21.0+archlinux1`.
+#[must_use]
+pub fn version_extra(shell: &str, banner: &[u8]) -> Option<String> {
+    let version = version(shell, banner)?;
+    let text = line_text(banner, MAX_FILE);
+    let start = text.find(&version)? + version.len();
+    let extra = text[start..].split_whitespace().next()?;
+    // A separate word, such as a build date, is not part of the version.
+    (!text[start..].starts_with(char::is_whitespace)).then(|| extra.to_string())
+}
+
+/// Finds a version string embedded in a shell binary, like `strings` piped to
+/// a search, e.g. `0.5.12` from `dash 0.5.12` or `dash-0.5.12`.
+///
+/// Only versions right after the shell's name count, so those of linked
+/// libraries, such as `GLIBC_2.34`, are not mistaken for it.
+#[must_use]
+pub fn embedded_version(shell: &str, binary: &[u8]) -> Option<String> {
+    let pattern = format!(
+        r"(?:^|[^a-z]){}[ /-]v?({SEMVER_PATTERN})",
+        regex::escape(shell)
+    );
+    let pattern = Regex::new(&pattern).ok()?;
+    binary[..binary.len().min(MAX_BINARY)]
+        .split(|byte| !(byte.is_ascii_graphic() || *byte == b' '))
+        .filter(|run| run.len() >= MIN_STRING)
+        .find_map(|run| {
+            // Printable ASCII is always UTF-8.
+            let run = std::str::from_utf8(run).ok()?;
+            Some(pattern.captures(run)?[1].to_string())
+        })
+}
@@ -0,0 +1,212 @@
+mod tests {
+    use crate::parse::*;
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn status_fields() {
+        let status = b"Name:\tbash\nPPid:\t7\nUid:\t1000\t1000\t1000\t1000\n";
+        assert_eq!(status_ppid(status).unwrap(), 7);
+        assert_eq!(status_uid(status).unwrap(), 1000);
+    }
+
+    #[test]
+    fn status_parse_error() {
+        let err = status_uid(b"Uid:\tbad\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = status_uid(b"Name:\tbash\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = status_ppid(b"PPid:\t-1\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn status_nspid_levels() {
+        assert_eq!(status_nspid(b"NSpid:\t4242\t7\n").unwrap(), [4242, 7]);
+        assert_eq!(status_nspid(b"NSpid:\t4242\n").unwrap(), [4242]);
+        let err = status_nspid(b"PPid:\t1\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = status_nspid(b"NSpid:\t4242\tx\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn stat_comm_with_parens() {
+        let stat = b"42 (a) b) (c) S 7 42 42 0";
+        assert_eq!(stat_ppid(stat).unwrap(), 7);
+        assert_eq!(stat_comm(stat).unwrap(), "a) b) (c");
+        assert_eq!(stat_session(stat).unwrap(), 42);
+    }
+
+    #[test]
+    fn stat_parse_error() {
+        for stat in [
+            &b"42 no parens"[..],
+            b"42 ) S 1 (",
+            b"42 (sh)",
+            b"42 (sh) S 99999999999",
+        ] {
+            let err = stat_ppid(stat).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+        let err = stat_session(b"42 (sh) S 1 42").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = stat_start_ticks(b"42 (sh) S 1 42").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn stat_arg_start_field() {
+        let stat = format!("42 (bash) S 1{} 140737488347136 0 0 0", " 0".repeat(43));
+        assert_eq!(
+            stat_arg_start(stat.as_bytes()).unwrap(),
+            140_737_488_347_136
+        );
+        let err = stat_arg_start(b"42 (bash) S 1 42 42 0").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn crlf_reads_like_lf() {
+        let status =
+            b"Name:\tbash\r\nPPid:\t7\r\nUid:\t1000\t1000\t1000\t1000\r\nNSpid:\t42\t7\r\n";
+        assert_eq!(status_ppid(status).unwrap(), 7);
+        assert_eq!(status_uid(status).unwrap(), 1000);
+        assert_eq!(status_nspid(status).unwrap(), [42, 7]);
+        let stat = b"42 (bash) S 7 42 42 0\r\n";
+        assert_eq!(stat_comm(stat).unwrap(), "bash");
+        assert_eq!(stat_session(stat).unwrap(), 42);
+        assert_eq!(
+            uptime(b"350.25 700.10\r\n").unwrap(),
+            Duration::from_millis(350_250)
+        );
+        let banner = b"Oils 0.22.0\r\n\r\nOil version 0.9.0\r\n";
+        assert_eq!(version("osh", banner), Some("0.22.0".to_string()));
+        assert_eq!(
+            version("pwsh", b"PowerShell 7.4.1\r\n"),
+            Some("7.4.1".to_string())
+        );
+        let environ = b"PROMPT=a\r\nb\0";
+        assert_eq!(environ_var(environ, "PROMPT"), Some("a\r\nb".to_string()));
+    }
+
+    #[test]
+    fn uptime_seconds() {
+        assert_eq!(
+            uptime(b"350.25 700.10\n").unwrap(),
+            Duration::from_millis(350_250)
+        );
+        for bad in [&b""[..], b"-1 0", b"NaN 0", b"1e400 0"] {
+            assert_eq!(uptime(bad).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn cmdline_splits_nul() {
+        assert_eq!(cmdline(b"-bash\0"), ["-bash"]);
+        assert_eq!(cmdline(b"sh\0-c\0\0"), ["sh", "-c", ""]);
+        assert_eq!(cmdline(b"zsh\0\xff\0"), ["zsh", "\u{fffd}"]);
+        assert!(cmdline(b"").is_empty());
+    }
+
+    #[test]
+    fn environ_finds_exact_key() {
+        let text = b"TMUX_PANE=%1\0TMUX=/tmp/tmux-1000/default\0EMPTY=\0junk\0";
+        let val = environ_var(text, "TMUX");
+        assert_eq!(val, Some("/tmp/tmux-1000/default".to_string()));
+        assert_eq!(environ_var(text, "EMPTY"), Some(String::new()));
+        assert_eq!(environ_var(text, "TMU"), None);
+        assert_eq!(environ(text).len(), 3);
+    }
+
+    #[test]
+    fn version_from_banner() {
+        let banner = b"GNU bash, version 5.2.15(1)-release";
+        assert_eq!(version("bash", banner), Some("5.2.15".to_string()));
+        let banner = b"@(#)MIRBSD KSH R59 2020/10/31";
+        assert_eq!(version("mksh", banner), Some("R59".to_string()));
+        assert_eq!(version("bash", b"\xff\xfe"), None);
+        assert_eq!(
+            version("xonsh", b"xonsh/0.15.1\n"),
+            Some("0.15.1".to_string())
+        );
+        let banner = b"BusyBox v1.37.0.git (2024-09-26 09:03:14 UTC) multi-call binary.";
+        assert_eq!(version("busybox", banner), Some("1.37.0".to_string()));
+        assert_eq!(version("busybox", b"ash: usage 2.1"), None);
+        let banner = b"Yet another shell, version 2.56.1\nCopyright (C) 2007-2023 magicant\n";
+        assert_eq!(version("yash", banner), Some("2.56.1".to_string()));
+        let banner = b"murex v6.0.1000\nLicense GPL v2\n";
+        assert_eq!(version("murex", banner), Some("6.0.1000".to_string()));
+        let banner = b"ion 1.0.0-alpha (x86_64-unknown-redox)\nrev 4d3d3e8\n";
+        assert_eq!(version("ion", banner), Some("1.0.0-alpha".to_string()));
+        let banner =
+            b"Oils 0.22.0\t\thttps://www.oilshell.org/\n\n[C++ translation]\nGit commit: abc\n";
+        assert_eq!(version("osh", banner), Some("0.22.0".to_string()));
+        let banner = b"Python 2.7.18\nOil version 0.9.0\nInterpreter version: 2.7.13\n";
+        assert_eq!(version("ysh", banner), Some("0.9.0".to_string()));
+    }
+
+    #[test]
+    fn version_extra_keeps_suffix() {
+        let banner = b"GNU bash, version 5.2.15(1)-release (x86_64-pc-linux-gnu)\n";
+        assert_eq!(
+            version_extra("bash", banner),
+            Some("(1)-release".to_string())
+        );
+        assert_eq!(
+            version_extra("elvish", b"0.21.0+archlinux1\n"),
+            Some("+archlinux1".to_string())
+        );
+        assert_eq!(version_extra("zsh", b"zsh 5.9 (x86_64-pc-linux-gnu)"), None);
+        assert_eq!(version_extra("fish", b"fish, version 3.7.1\n"), None);
+        let banner = b"  version         sh (AT&T Research) 93u+ 2012-08-01\n";
+        assert_eq!(version_extra("ksh", banner), None);
+        assert_eq!(version_extra("ion", b"ion 1.0.0-alpha (x86_64)"), None);
+    }
+
+    #[test]
+    fn embedded_version_after_name() {
+        let binary = b"\x7fELF\x02\x01GLIBC_2.34\0libc.so.6\0dash 0.5.12\0%s: %s\0";
+        assert_eq!(embedded_version("dash", binary), Some("0.5.12".to_string()));
+        let binary = b"\0\x01/usr/share/doc/dash-0.5.11.5/README\0";
+        assert_eq!(embedded_version("dash", binary), Some("0.5.11".to_string()));
+        assert_eq!(embedded_version("ash", b"\0dash 0.5.12\0"), None);
+        assert_eq!(
+            embedded_version("dash", b"\0GLIBC_2.34\0GCC 13.2.0\0"),
+            None
+        );
+    }
+
+    #[test]
+    fn version_from_legacy_banners() {
+        let banner = b"GNU bash, version 3.2.57(1)-release (arm64-apple-darwin23)";
+        assert_eq!(version("bash", banner), Some("3.2.57".to_string()));
+        assert_eq!(
+            version("ksh", b"Version M-11/16/88i"),
+            Some("88i".to_string())
+        );
+        assert_eq!(version("ksh", b"Version 11/16/88"), Some("88".to_string()));
+        let banner = b"  version         sh (AT&T Research) 93u+ 2012-08-01\n";
+        assert_eq!(version("ksh", banner), Some("93u+".to_string()));
+        let banner = b"  version         sh (AT&T Research) 93u+m/1.0.8 2024-01-01\n";
+        assert_eq!(version("ksh", banner), Some("1.0.8".to_string()));
+        assert_eq!(version("ksh", b"ksh: --version: bad option(s)"), None);
+        let banner = b"Version AJM 93u+ 2012-08-01";
+        assert_eq!(version("ksh", banner), Some("93u+".to_string()));
+        let banner = b"Version AJM 93u+m/1.0.8 2024-01-01";
+        assert_eq!(version("ksh", banner), Some("1.0.8".to_string()));
+        let banner = b"@(#)PD KSH v5.2.14 99/07/13.2";
+        assert_eq!(version("ksh", banner), Some("5.2.14".to_string()));
+    }
+
+    #[test]
+    fn oversized_input_is_bounded() {
+        let mut status = vec![b'x'; MAX_FILE];
+        status.extend_from_slice(b"\nPPid:\t1\n");
+        let err = status_ppid(&status).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let mut banner = vec![b' '; MAX_FILE * 4];
+        banner.extend_from_slice(b"5.9");
+        assert_eq!(version("zsh", &banner), None);
+    }
+}
@@ -0,0 +1,35 @@
+//! Minimal `/etc/passwd` parsing, without going through NSS.
+
+/// The fields of a passwd entry needed to resolve a login shell.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Entry<'a> {
+    pub name: &'a str,
+    pub uid: u32,
+    pub shell: &'a str,
+}
+
+/// Parses a single `name:password:uid:gid:gecos:home:shell` line.
+pub fn parse_entry(line: &str) -> Option<Entry<'_>> {
+    let fields: Vec<&str> = line.trim_end().split(':').collect();
+    let [name, _, uid, _, _, _, shell] = fields[..] else {
+        return None;
+    };
+    let uid = uid.parse().ok()?;
+    Some(Entry { name, uid, shell })
+}
+
+/// Returns the entry of `uid`, skipping comments and malformed lines.
+pub fn entry_by_uid(text: &str, uid: u32) -> Option<Entry<'_>> {
+    entries(text).find(|entry| entry.uid == uid)
+}
+
+/// Returns the entry of the user called `name`.
+pub fn entry_by_name<'a>(text: &'a str, name: &str) -> Option<Entry<'a>> {
+    entries(text).find(|entry| entry.name == name)
+}
+
+fn entries(text: &str) -> impl Iterator<Item = Entry<'_>> {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(parse_entry)
+}
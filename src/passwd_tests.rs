@@ -0,0 +1,48 @@
+mod tests {
+    use crate::passwd::*;
+
+    const PASSWD: &str = "\
+# local users
+root:x:0:0:root:/root:/bin/bash
+broken line
+alice:x:1000:1000:Alice,,,:/home/alice:/usr/bin/zsh
+svc:x:999:999::/var/lib/svc:/usr/sbin/nologin
+";
+
+    #[test]
+    fn parse_entry_ok() {
+        let entry = parse_entry("alice:x:1000:1000:Alice:/home/alice:/usr/bin/zsh\n").unwrap();
+        let want = Entry {
+            name: "alice",
+            uid: 1000,
+            shell: "/usr/bin/zsh",
+        };
+        assert_eq!(entry, want);
+    }
+
+    #[test]
+    fn parse_entry_malformed() {
+        assert_eq!(parse_entry("alice:x:1000"), None);
+        assert_eq!(parse_entry("alice:x:bad:1000::/home/alice:/bin/sh"), None);
+    }
+
+    #[test]
+    fn entry_by_uid_found() {
+        let entry = entry_by_uid(PASSWD, 1000).unwrap();
+        assert_eq!(entry.name, "alice");
+        assert_eq!(entry.shell, "/usr/bin/zsh");
+    }
+
+    #[test]
+    fn entry_by_uid_missing() {
+        assert_eq!(entry_by_uid(PASSWD, 4242), None);
+    }
+
+    #[test]
+    fn entry_by_name_found() {
+        let entry = entry_by_name(PASSWD, "svc").unwrap();
+        assert_eq!(entry.uid, 999);
+        assert_eq!(entry.shell, "/usr/sbin/nologin");
+        assert_eq!(entry_by_name(PASSWD, "bob"), None);
+    }
+}
@@ -0,0 +1,194 @@
+//! A flat, versioned detection result shared by every interop surface.
+//!
+//! [`DetectionRecord`] has a fixed field set in which every optional value is
+//! spelled out, so JSON and YAML output and future bindings describe a shell
+//! the same way. Adding, removing or retyping a field bumps [`SCHEMA`].
+//!
+//! ```
+//! use shellver::record::DetectionRecord;
+//!
+//! let record = DetectionRecord { name: "bash".to_string(), ..DetectionRecord::default() };
+//! assert!(record.to_json().starts_with(r#"{"schema":2,"name":"bash","version":null"#));
+//! ```
+use crate::render::json_value;
+use crate::{Shell, ShellNotFound, Warning};
+use std::io;
+
+/// Version of the record layout, written as its `schema` field.
+pub const SCHEMA: u32 = 2;
+
+/// One detected shell as plain data, see the [module docs](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectionRecord {
+    /// Layout version, [`SCHEMA`] for records built by this crate.
+    pub schema: u32,
+    /// Shell name, see [`Shell::name`].
+    pub name: String,
+    /// Parsed version, see [`Shell::version`].
+    pub version: Option<String>,
+    /// Executable path, see [`Shell::path`].
+    pub path: Option<String>,
+    /// Where the shell was found, see [`crate::Source::as_str`].
+    pub source: String,
+    /// Process id of a running shell, see [`Shell::pid`].
+    pub pid: Option<u32>,
+    /// Parent process id of a running shell, see [`Shell::ppid`].
+    pub ppid: Option<u32>,
+    /// Owning user id, see [`Shell::uid`].
+    pub uid: Option<u32>,
+    /// Warnings, see [`Shell::warnings`].
+    pub warnings: Vec<WarningRecord>,
+}
+
+/// A [`Warning`] as its stable code and English message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarningRecord {
+    /// Stable code such as `W001`, see [`Warning::code`].
+    pub code: String,
+    /// Human readable description.
+    pub message: String,
+}
+
+impl From<&Warning> for WarningRecord {
+    fn from(warning: &Warning) -> Self {
+        Self {
+            code: warning.code().to_string(),
+            message: warning.to_string(),
+        }
+    }
+}
+
+impl Default for DetectionRecord {
+    fn default() -> Self {
+        Self {
+            schema: SCHEMA,
+            name: String::new(),
+            version: None,
+            path: None,
+            source: String::new(),
+            pid: None,
+            ppid: None,
+            uid: None,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl From<&Shell> for DetectionRecord {
+    fn from(shell: &Shell) -> Self {
+        Self {
+            schema: SCHEMA,
+            name: shell.name().to_string(),
+            version: shell.version(),
+            path: shell.path().map(|path| path.display().to_string()),
+            source: shell.source().as_str().to_string(),
+            pid: shell.pid(),
+            ppid: shell.ppid(),
+            uid: shell.uid(),
+            warnings: shell.warnings().iter().map(WarningRecord::from).collect(),
+        }
+    }
+}
+
+impl DetectionRecord {
+    /// Returns the scalar fields in output order, encoded as JSON values.
+    pub(crate) fn scalars(&self) -> [(&'static str, String); 8] {
+        let number =
+            |value: Option<u32>| value.map_or_else(|| "null".to_string(), |n| n.to_string());
+        [
+            ("schema", self.schema.to_string()),
+            ("name", json_value(Some(&self.name))),
+            ("version", json_value(self.version.as_deref())),
+            ("path", json_value(self.path.as_deref())),
+            ("source", json_value(Some(&self.source))),
+            ("pid", number(self.pid)),
+            ("ppid", number(self.ppid)),
+            ("uid", number(self.uid)),
+        ]
+    }
+
+    /// Encodes the record as a compact JSON object, as written by
+    /// `--output json`.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut body: Vec<_> = self
+            .scalars()
+            .iter()
+            .map(|(key, value)| format!("\"{key}\":{value}"))
+            .collect();
+        let warnings = self.warnings.iter().map(|warning| {
+            let code = json_value(Some(&warning.code));
+            let message = json_value(Some(&warning.message));
+            format!("{{\"code\":{code},\"message\":{message}}}")
+        });
+        body.push(format!(
+            "\"warnings\":[{}]",
+            warnings.collect::<Vec<_>>().join(",")
+        ));
+        format!("{{{}}}", body.join(","))
+    }
+}
+
+/// A failed detection as plain data, written by `--output json` in place of
+/// a bare error message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorRecord {
+    /// Stable kind: `not_found`, `invalid_input` or `io`. The CLI reports
+    /// unreadable process chains as `proc` and `--strict` failing on an
+    /// unknown version as `no_version`.
+    pub kind: String,
+    /// Human readable description, in English.
+    pub message: String,
+    /// Processes passed over when no shell was found, see
+    /// [`ShellNotFound::chain`].
+    pub chain: Vec<(u32, String)>,
+    /// Where the walk stopped, see [`crate::ChainEnd::as_str`].
+    pub end: Option<String>,
+    /// See [`ShellNotFound::nested_pid_namespace`].
+    pub nested_pid_namespace: bool,
+}
+
+impl From<&io::Error> for ErrorRecord {
+    fn from(err: &io::Error) -> Self {
+        let not_found = err
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<ShellNotFound>());
+        let kind = match err.kind() {
+            _ if not_found.is_some() => "not_found",
+            io::ErrorKind::NotFound => "not_found",
+            io::ErrorKind::InvalidInput => "invalid_input",
+            _ => "io",
+        };
+        Self {
+            kind: kind.to_string(),
+            message: err.to_string(),
+            chain: not_found
+                .map(|not_found| not_found.chain.clone())
+                .unwrap_or_default(),
+            end: not_found.map(|not_found| not_found.end.as_str().to_string()),
+            nested_pid_namespace: not_found.is_some_and(|not_found| not_found.nested_pid_namespace),
+        }
+    }
+}
+
+impl ErrorRecord {
+    /// Encodes the record as a compact JSON object under an `error` key,
+    /// e.g. `{"error":{"kind":"not_found",...}}`. `hops` counts the
+    /// processes in `chain`.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let chain = self.chain.iter().map(|(pid, comm)| {
+            let comm = json_value(Some(comm));
+            format!("{{\"pid\":{pid},\"comm\":{comm}}}")
+        });
+        format!(
+            "{{\"error\":{{\"kind\":{},\"message\":{},\"hops\":{},\"end\":{},\"chain\":[{}],\"nested_pid_namespace\":{}}}}}",
+            json_value(Some(&self.kind)),
+            json_value(Some(&self.message)),
+            self.chain.len(),
+            json_value(self.end.as_deref()),
+            chain.collect::<Vec<_>>().join(","),
+            self.nested_pid_namespace,
+        )
+    }
+}
@@ -0,0 +1,452 @@
+//! Output renderers shared by the CLI and embedding binaries.
+//!
+//! Every format implements [`Renderer`], so adding a new one is additive:
+//!
+//! ```no_run
+//! use shellver::render::{Json, Renderer};
+//! use shellver::Shell;
+//!
+//! let shell = Shell::detect()?;
+//! Json.render(&shell, &mut std::io::stdout())?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! [`Format`] picks a renderer by the names `--output` accepts, so embedding
+//! tools produce byte-identical output to the CLI:
+//!
+//! ```no_run
+//! use shellver::render::Format;
+//! use shellver::Shell;
+//!
+//! let format: Format = "json".parse()?;
+//! print!("{}", format.to_text(&Shell::detect()?));
+//! # Ok::<(), std::io::Error>(())
+//! ```
+use crate::record::DetectionRecord;
+use crate::{Family, Shell, Source};
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// Writes a detected [`Shell`] in a specific format.
+pub trait Renderer {
+    /// # Errors
+    ///
+    /// Returns an error if writing to `out` fails.
+    fn render(&self, shell: &Shell, out: &mut dyn Write) -> io::Result<()>;
+
+    /// Writes several shells, keeping the order they are given in. The
+    /// default writes one record after the other.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `out` fails.
+    fn render_all(&self, shells: &[Shell], out: &mut dyn Write) -> io::Result<()> {
+        shells.iter().try_for_each(|shell| self.render(shell, out))
+    }
+}
+
+/// `name version` on a single line, the CLI default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Plain;
+
+/// A single JSON object with `null` for missing values, laid out as a
+/// [`DetectionRecord`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+/// A YAML mapping with quoted string values, laid out as a
+/// [`DetectionRecord`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Yaml;
+
+/// A header row followed by an aligned value row.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Table;
+
+/// A multi-line human summary with one `label: value` line per detail.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Long;
+
+/// The stable line format for scripts, `--porcelain`.
+///
+/// Version 1 writes `name`, `version` and `path` separated by tabs, one shell
+/// per line, with missing values left empty. Backslashes, tabs and newlines
+/// in values are escaped as `\\`, `\t` and `\n`. This layout never changes;
+/// a different one would be added as a new version.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Porcelain;
+
+/// Assignments of `SHELLVER_NAME`, `SHELLVER_VERSION` and `SHELLVER_PATH` in
+/// the syntax of the detected shell itself, for `eval "$(shellver env)"`.
+///
+/// POSIX shells get `export`, fish `set -gx`, csh and tcsh `setenv`,
+/// PowerShell `$env:`, nu `$env.`, elvish `set-env`, xonsh `$` and rc and es
+/// a plain assignment. Missing values are set empty. Other shells, such as
+/// ion, murex and ysh, get an [`io::ErrorKind::Unsupported`] error rather
+/// than output they cannot evaluate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Env;
+
+/// A user supplied layout where `{field}` placeholders are replaced.
+///
+/// Besides the structured fields, `{semver}`, `{major}`, `{minor}` and
+/// `{patch}` give the normalized version, see [`Shell::semver`] and
+/// [`Shell::major`], `{pid}` the process id, `{family}` the
+/// [`Family`](crate::Family) label and `{fingerprint}` the session key of
+/// [`Shell::fingerprint`]. Unknown placeholders are kept verbatim and missing
+/// values render empty.
+#[derive(Debug, Clone)]
+pub struct Template {
+    layout: String,
+}
+
+impl Template {
+    /// Creates a template from a layout such as `"{name} {version}"`.
+    #[must_use]
+    pub fn new(layout: impl Into<String>) -> Self {
+        Self {
+            layout: layout.into(),
+        }
+    }
+}
+
+/// One of the CLI's output formats, as selected with `--output`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Format {
+    /// See [`Plain`].
+    Plain,
+    /// See [`Json`].
+    Json,
+    /// See [`Yaml`].
+    Yaml,
+    /// See [`Table`].
+    Table,
+    /// See [`Long`].
+    Long,
+    /// See [`Porcelain`].
+    Porcelain,
+    /// See [`Template`], with its layout.
+    Template(String),
+}
+
+impl Format {
+    /// Returns the renderer writing this format.
+    #[must_use]
+    pub fn renderer(&self) -> Box<dyn Renderer> {
+        match self {
+            Self::Plain => Box::new(Plain),
+            Self::Json => Box::new(Json),
+            Self::Yaml => Box::new(Yaml),
+            Self::Table => Box::new(Table),
+            Self::Long => Box::new(Long),
+            Self::Porcelain => Box::new(Porcelain),
+            Self::Template(layout) => Box::new(Template::new(layout.as_str())),
+        }
+    }
+
+    /// Renders `shell` to a string, exactly as the CLI prints it.
+    #[must_use]
+    pub fn to_text(&self, shell: &Shell) -> String {
+        let mut out = Vec::new();
+        // Writing to a `Vec` cannot fail and every renderer writes UTF-8.
+        _ = self.renderer().render(shell, &mut out);
+        String::from_utf8_lossy(&out).into_owned()
+    }
+
+    /// Renders several shells to a string, like `shellver survey`.
+    #[must_use]
+    pub fn to_text_all(&self, shells: &[Shell]) -> String {
+        let mut out = Vec::new();
+        _ = self.renderer().render_all(shells, &mut out);
+        String::from_utf8_lossy(&out).into_owned()
+    }
+}
+
+/// Parses the names `--output` accepts, except `template`, which needs a
+/// layout and is built as [`Format::Template`] instead.
+impl FromStr for Format {
+    type Err = io::Error;
+
+    fn from_str(name: &str) -> io::Result<Self> {
+        Ok(match name {
+            "plain" => Self::Plain,
+            "json" => Self::Json,
+            "yaml" => Self::Yaml,
+            "table" => Self::Table,
+            "long" => Self::Long,
+            "porcelain" => Self::Porcelain,
+            _ => {
+                let msg = format!("unknown output format: {name}");
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+            }
+        })
+    }
+}
+
+/// Columns of the table and placeholders of templates, in output order.
+const FIELDS: [&str; 4] = ["name", "version", "path", "source"];
+
+/// Extra template placeholders derived from the fields.
+const DERIVED: [&str; 7] = [
+    "semver",
+    "major",
+    "minor",
+    "patch",
+    "pid",
+    "family",
+    "fingerprint",
+];
+
+fn field(shell: &Shell, key: &str) -> Option<String> {
+    match key {
+        "name" => Some(shell.name().to_string()),
+        "version" => shell.version(),
+        "path" => shell.path().map(|path| path.display().to_string()),
+        "source" => Some(shell.source().as_str().to_string()),
+        "semver" => shell.semver(),
+        "major" => shell.major().map(|major| major.to_string()),
+        "minor" | "patch" => {
+            let semver = shell.semver()?;
+            let index = if key == "minor" { 1 } else { 2 };
+            semver.split('.').nth(index).map(str::to_string)
+        }
+        "pid" => shell.pid().map(|pid| pid.to_string()),
+        "family" => Some(shell.family().as_str().to_string()),
+        "fingerprint" => Some(shell.fingerprint()),
+        _ => None,
+    }
+}
+
+impl Renderer for Plain {
+    fn render(&self, shell: &Shell, out: &mut dyn Write) -> io::Result<()> {
+        let name = shell.name();
+        let version = shell.version().unwrap_or_default();
+        writeln!(out, "{name} {version}")
+    }
+}
+
+fn json_object(shell: &Shell) -> String {
+    DetectionRecord::from(shell).to_json()
+}
+
+impl Renderer for Json {
+    fn render(&self, shell: &Shell, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{}", json_object(shell))
+    }
+
+    /// Writes a single JSON array.
+    fn render_all(&self, shells: &[Shell], out: &mut dyn Write) -> io::Result<()> {
+        let items = shells.iter().map(json_object).collect::<Vec<_>>();
+        writeln!(out, "[{}]", items.join(","))
+    }
+}
+
+fn yaml_mapping(shell: &Shell, indent: &str, out: &mut dyn Write) -> io::Result<()> {
+    let record = DetectionRecord::from(shell);
+    for (key, value) in record.scalars() {
+        writeln!(out, "{indent}{key}: {value}")?;
+    }
+    if record.warnings.is_empty() {
+        return writeln!(out, "{indent}warnings: []");
+    }
+    writeln!(out, "{indent}warnings:")?;
+    for warning in &record.warnings {
+        writeln!(out, "{indent}  - code: {}", json_value(Some(&warning.code)))?;
+        writeln!(
+            out,
+            "{indent}    message: {}",
+            json_value(Some(&warning.message))
+        )?;
+    }
+    Ok(())
+}
+
+impl Renderer for Yaml {
+    fn render(&self, shell: &Shell, out: &mut dyn Write) -> io::Result<()> {
+        yaml_mapping(shell, "", out)
+    }
+
+    /// Writes a single YAML sequence of mappings.
+    fn render_all(&self, shells: &[Shell], out: &mut dyn Write) -> io::Result<()> {
+        if shells.is_empty() {
+            return writeln!(out, "[]");
+        }
+        for shell in shells {
+            let mut item = Vec::new();
+            yaml_mapping(shell, "  ", &mut item)?;
+            out.write_all(b"- ")?;
+            out.write_all(&item[2..])?;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for Table {
+    fn render(&self, shell: &Shell, out: &mut dyn Write) -> io::Result<()> {
+        self.render_all(std::slice::from_ref(shell), out)
+    }
+
+    /// Writes one header row followed by one aligned row per shell.
+    fn render_all(&self, shells: &[Shell], out: &mut dyn Write) -> io::Result<()> {
+        let header = FIELDS.map(str::to_uppercase);
+        let rows: Vec<_> = shells
+            .iter()
+            .map(|shell| FIELDS.map(|key| field(shell, key).unwrap_or_else(|| "-".to_string())))
+            .collect();
+        let widths = FIELDS.map(str::len);
+        let widths = rows.iter().fold(widths, |mut widths, row| {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+            widths
+        });
+        let line = |cells: &[String]| {
+            let cells = cells
+                .iter()
+                .zip(widths)
+                .map(|(cell, w)| format!("{cell:<w$}"));
+            cells.collect::<Vec<_>>().join("  ").trim_end().to_string()
+        };
+        writeln!(out, "{}", line(&header))?;
+        rows.iter()
+            .try_for_each(|row| writeln!(out, "{}", line(row)))
+    }
+}
+
+impl Renderer for Long {
+    fn render(&self, shell: &Shell, out: &mut dyn Write) -> io::Result<()> {
+        let lines = [
+            ("name", Some(shell.name().to_string())),
+            ("version", shell.version()),
+            ("path", field(shell, "path")),
+            ("pid", shell.pid().map(|pid| pid.to_string())),
+            ("mode", shell.mode().map(str::to_string)),
+            ("provenance", Some(shell.source().as_str().to_string())),
+            ("confidence", Some(confidence(shell).to_string())),
+        ];
+        for (label, value) in lines {
+            let value = value.unwrap_or_else(|| "-".to_string());
+            writeln!(out, "{:<12}{value}", format!("{label}:"))?;
+        }
+        shell.warnings().iter().try_for_each(|warning| {
+            writeln!(out, "{:<12}[{}] {warning}", "warning:", warning.code())
+        })
+    }
+
+    /// Writes the summaries separated by blank lines.
+    fn render_all(&self, shells: &[Shell], out: &mut dyn Write) -> io::Result<()> {
+        for (i, shell) in shells.iter().enumerate() {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            self.render(shell, out)?;
+        }
+        Ok(())
+    }
+}
+
+// A running process without caveats is certain, anything taken from
+// configuration may not be what the user actually runs.
+fn confidence(shell: &Shell) -> &'static str {
+    match (shell.source(), shell.warnings().is_empty()) {
+        (Source::Process, true) => "high",
+        (Source::Process, false) => "medium",
+        _ => "low",
+    }
+}
+
+impl Renderer for Porcelain {
+    fn render(&self, shell: &Shell, out: &mut dyn Write) -> io::Result<()> {
+        let escape = |value: Option<String>| {
+            let value = value.unwrap_or_default();
+            value
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+        };
+        let cells = ["name", "version", "path"].map(|key| escape(field(shell, key)));
+        writeln!(out, "{}", cells.join("\t"))
+    }
+}
+
+impl Renderer for Env {
+    fn render(&self, shell: &Shell, out: &mut dyn Write) -> io::Result<()> {
+        for (key, field_key) in [
+            ("SHELLVER_NAME", "name"),
+            ("SHELLVER_VERSION", "version"),
+            ("SHELLVER_PATH", "path"),
+        ] {
+            let value = field(shell, field_key).unwrap_or_default();
+            let doubled = || value.replace('\'', "''");
+            let escaped = || value.replace('\\', "\\\\").replace('\'', "\\'");
+            let line = match (shell.name(), shell.family()) {
+                ("fish", _) => format!("set -gx {key} '{}';", escaped()),
+                // Single quotes cannot be escaped in nu, a raw string takes them.
+                ("nu", _) if value.contains('\'') => format!("$env.{key} = r#'{value}'#"),
+                ("nu", _) => format!("$env.{key} = '{value}'"),
+                ("elvish", _) => format!("set-env {key} '{}'", doubled()),
+                ("xonsh", _) => format!("${key} = '{}'", escaped()),
+                // Every variable of rc and es is in the environment.
+                ("rc", _) => format!("{key}='{}'", doubled()),
+                ("es", _) => format!("{key} = '{}'", doubled()),
+                (_, Family::Csh) => format!("setenv {key} '{}';", value.replace('\'', "'\\''")),
+                (_, Family::PowerShell) => format!("$env:{key} = '{}'", doubled()),
+                (_, Family::Bourne) => format!("export {key}='{}';", value.replace('\'', "'\\''")),
+                (name, Family::Other) => {
+                    let msg = format!("no env syntax for {name}");
+                    return Err(io::Error::new(io::ErrorKind::Unsupported, msg));
+                }
+            };
+            writeln!(out, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for Template {
+    fn render(&self, shell: &Shell, out: &mut dyn Write) -> io::Result<()> {
+        // One pass, so placeholders inside values, e.g. a path containing
+        // `{pid}`, are kept as they are. Unknown ones are too.
+        let mut text = String::with_capacity(self.layout.len());
+        let mut rest = self.layout.as_str();
+        while let Some(open) = rest.find('{') {
+            text.push_str(&rest[..open]);
+            rest = &rest[open + 1..];
+            let key = rest.find('}').map(|close| &rest[..close]);
+            match key.filter(|key| FIELDS.contains(key) || DERIVED.contains(key)) {
+                Some(key) => {
+                    text.push_str(&field(shell, key).unwrap_or_default());
+                    rest = &rest[key.len() + 1..];
+                }
+                None => text.push('{'),
+            }
+        }
+        text.push_str(rest);
+        writeln!(out, "{text}")
+    }
+}
+
+/// Encodes `value` as a JSON string, or `null` when missing.
+pub(crate) fn json_value(value: Option<&str>) -> String {
+    let Some(value) = value else {
+        return "null".to_string();
+    };
+    let mut text = String::with_capacity(value.len() + 2);
+    text.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => text.push_str("\\\""),
+            '\\' => text.push_str("\\\\"),
+            '\n' => text.push_str("\\n"),
+            '\r' => text.push_str("\\r"),
+            '\t' => text.push_str("\\t"),
+            // Writing into a `String` cannot fail.
+            ch if u32::from(ch) < 0x20 => _ = write!(text, "\\u{:04x}", u32::from(ch)),
+            ch => text.push(ch),
+        }
+    }
+    text.push('"');
+    text
+}
@@ -0,0 +1,294 @@
+mod tests {
+    use crate::record::{DetectionRecord, ErrorRecord, SCHEMA};
+    use crate::render::*;
+    use crate::{ChainEnd, Shell, ShellNotFound, Source, Warning};
+    use std::io;
+
+    fn bash() -> Shell {
+        let mut shell = Shell::new("bash", Source::Process);
+        shell.version = Some("5.3.9".to_string());
+        shell.path = Some("/usr/bin/bash".into());
+        shell
+    }
+
+    fn dash() -> Shell {
+        Shell::new("dash", Source::Process)
+    }
+
+    fn render_all(renderer: &dyn Renderer, shells: &[Shell]) -> String {
+        let mut out = Vec::new();
+        renderer.render_all(shells, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn render(renderer: &dyn Renderer, shell: &Shell) -> String {
+        let mut out = Vec::new();
+        renderer.render(shell, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn plain_output() {
+        assert_eq!(render(&Plain, &bash()), "bash 5.3.9\n");
+        assert_eq!(render(&Plain, &dash()), "dash \n");
+    }
+
+    #[test]
+    fn json_output() {
+        assert_eq!(
+            render(&Json, &bash()),
+            "{\"schema\":2,\"name\":\"bash\",\"version\":\"5.3.9\",\"path\":\"/usr/bin/bash\",\"source\":\"process\",\"pid\":null,\"ppid\":null,\"uid\":null,\"warnings\":[]}\n"
+        );
+        assert_eq!(
+            render(&Json, &dash()),
+            "{\"schema\":2,\"name\":\"dash\",\"version\":null,\"path\":null,\"source\":\"process\",\"pid\":null,\"ppid\":null,\"uid\":null,\"warnings\":[]}\n"
+        );
+    }
+
+    #[test]
+    fn json_escapes_strings() {
+        let shell = Shell::new("a\"b\\c\n\u{1}", Source::Process);
+        assert_eq!(
+            render(&Json, &shell),
+            "{\"schema\":2,\"name\":\"a\\\"b\\\\c\\n\\u0001\",\"version\":null,\"path\":null,\"source\":\"process\",\"pid\":null,\"ppid\":null,\"uid\":null,\"warnings\":[]}\n"
+        );
+    }
+
+    #[test]
+    fn yaml_output() {
+        assert_eq!(
+            render(&Yaml, &bash()),
+            "schema: 2\nname: \"bash\"\nversion: \"5.3.9\"\npath: \"/usr/bin/bash\"\nsource: \"process\"\npid: null\nppid: null\nuid: null\nwarnings: []\n"
+        );
+        assert_eq!(
+            render(&Yaml, &dash()),
+            "schema: 2\nname: \"dash\"\nversion: null\npath: null\nsource: \"process\"\npid: null\nppid: null\nuid: null\nwarnings: []\n"
+        );
+    }
+
+    #[test]
+    fn table_output() {
+        assert_eq!(
+            render(&Table, &bash()),
+            "NAME  VERSION  PATH           SOURCE\nbash  5.3.9    /usr/bin/bash  process\n"
+        );
+        assert_eq!(
+            render(&Table, &dash()),
+            "NAME  VERSION  PATH  SOURCE\ndash  -        -     process\n"
+        );
+    }
+
+    #[test]
+    fn template_output() {
+        let template = Template::new("{name}@{version} {path} {unknown}");
+        assert_eq!(
+            render(&template, &bash()),
+            "bash@5.3.9 /usr/bin/bash {unknown}\n"
+        );
+        assert_eq!(render(&template, &dash()), "dash@  {unknown}\n");
+        let template = Template::new("{semver} {major}");
+        assert_eq!(render(&template, &bash()), "5.3.9 5\n");
+        let template = Template::new("{name} {major}.{minor}.{patch} {family} {pid}");
+        assert_eq!(render(&template, &bash()), "bash 5.3.9 bourne \n");
+        assert_eq!(render(&template, &dash()), "dash .. bourne \n");
+        // Values are inserted as they are, even when they look like placeholders.
+        let mut shell = bash();
+        shell.path = Some("/opt/{pid}/{name}/bash".into());
+        let template = Template::new("{{path}} {name} {pid}{");
+        assert_eq!(
+            render(&template, &shell),
+            "{/opt/{pid}/{name}/bash} bash {\n"
+        );
+    }
+
+    #[test]
+    fn porcelain_output() {
+        let text = render_all(&Porcelain, &[bash(), dash()]);
+        assert_eq!(text, "bash\t5.3.9\t/usr/bin/bash\ndash\t\t\n");
+        let mut shell = bash();
+        shell.path = Some("/opt/my\tshells\\bash".into());
+        assert_eq!(
+            render(&Porcelain, &shell),
+            "bash\t5.3.9\t/opt/my\\tshells\\\\bash\n"
+        );
+    }
+
+    #[test]
+    fn env_output_per_family() {
+        assert_eq!(
+            render(&Env, &bash()),
+            concat!(
+                "export SHELLVER_NAME='bash';\n",
+                "export SHELLVER_VERSION='5.3.9';\n",
+                "export SHELLVER_PATH='/usr/bin/bash';\n",
+            )
+        );
+        let mut shell = Shell::new("fish", Source::Process);
+        shell.path = Some("/opt/it's/fish".into());
+        let text = render(&Env, &shell);
+        assert!(text.starts_with("set -gx SHELLVER_NAME 'fish';\n"));
+        assert!(text.ends_with("set -gx SHELLVER_PATH '/opt/it\\'s/fish';\n"));
+        let text = render(&Env, &Shell::new("tcsh", Source::Process));
+        assert!(text.starts_with("setenv SHELLVER_NAME 'tcsh';\n"));
+        let mut shell = Shell::new("pwsh", Source::Process);
+        shell.version = Some("7.4'1".to_string());
+        let text = render(&Env, &shell);
+        assert!(text.contains("$env:SHELLVER_VERSION = '7.4''1'\n"));
+        let mut shell = dash();
+        shell.path = Some("/it's".into());
+        assert!(render(&Env, &shell).ends_with("export SHELLVER_PATH='/it'\\''s';\n"));
+        let own = |name: &str| {
+            let mut shell = Shell::new(name, Source::Process);
+            shell.path = Some("/it's".into());
+            render(&Env, &shell)
+        };
+        assert!(own("nu").starts_with("$env.SHELLVER_NAME = 'nu'\n"));
+        assert!(own("nu").ends_with("$env.SHELLVER_PATH = r#'/it's'#\n"));
+        assert!(own("elvish").ends_with("set-env SHELLVER_PATH '/it''s'\n"));
+        assert!(own("xonsh").ends_with("$SHELLVER_PATH = '/it\\'s'\n"));
+        assert!(own("rc").ends_with("SHELLVER_PATH='/it''s'\n"));
+        assert!(own("es").ends_with("SHELLVER_PATH = '/it''s'\n"));
+        for name in ["ion", "murex", "ysh"] {
+            let mut out = Vec::new();
+            let err = Env
+                .render(&Shell::new(name, Source::Process), &mut out)
+                .unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+            assert!(out.is_empty());
+        }
+    }
+
+    #[test]
+    fn warnings_output() {
+        let mut shell = bash();
+        shell.warnings.push(Warning::VersionFromPath);
+        assert!(
+            render(&Json, &shell)
+                .ends_with(",\"warnings\":[{\"code\":\"W002\",\"message\":\"version from PATH binary, not running process\"}]}\n")
+        );
+        assert!(
+            render(&Yaml, &shell)
+                .ends_with("warnings:\n  - code: \"W002\"\n    message: \"version from PATH binary, not running process\"\n")
+        );
+    }
+
+    #[test]
+    fn render_all_keeps_order() {
+        let shells = [dash(), bash()];
+        assert_eq!(render_all(&Plain, &shells), "dash \nbash 5.3.9\n");
+        let json = render_all(&Json, &shells);
+        assert!(json.starts_with("[{\"schema\":2,\"name\":\"dash\""));
+        assert!(json.contains("},{\"schema\":2,\"name\":\"bash\""));
+        assert!(json.ends_with("}]\n"));
+        assert_eq!(
+            render_all(&Table, &shells),
+            "NAME  VERSION  PATH           SOURCE\ndash  -        -              process\nbash  5.3.9    /usr/bin/bash  process\n"
+        );
+    }
+
+    #[test]
+    fn table_aligns_non_ascii_cells() {
+        let mut bash = bash();
+        bash.path = Some("/home/zoë/bin/bash".into());
+        assert_eq!(
+            render_all(&Table, &[bash, dash()]),
+            "NAME  VERSION  PATH                SOURCE\nbash  5.3.9    /home/zoë/bin/bash  process\ndash  -        -                   process\n"
+        );
+    }
+
+    #[test]
+    fn json_record_numbers() {
+        let mut shell = bash();
+        shell.pid = Some(42);
+        shell.uid = Some(1000);
+        let record = DetectionRecord::from(&shell);
+        assert_eq!(record.schema, SCHEMA);
+        assert_eq!(record.ppid, None);
+        shell.warnings.push(Warning::CommExeMismatch);
+        let record = DetectionRecord::from(&shell);
+        assert_eq!(record.warnings[0].code, Warning::CommExeMismatch.code());
+        assert!(render(&Json, &shell).contains(",\"pid\":42,\"ppid\":null,\"uid\":1000,"));
+        assert_eq!(render(&Json, &shell), format!("{}\n", record.to_json()));
+    }
+
+    #[test]
+    fn render_all_yaml_sequence() {
+        assert_eq!(
+            render_all(&Yaml, &[dash()]),
+            "- schema: 2\n  name: \"dash\"\n  version: null\n  path: null\n  source: \"process\"\n  pid: null\n  ppid: null\n  uid: null\n  warnings: []\n"
+        );
+        assert_eq!(render_all(&Yaml, &[]), "[]\n");
+        assert_eq!(render_all(&Json, &[]), "[]\n");
+    }
+
+    #[test]
+    fn long_output() {
+        let mut shell = bash();
+        shell.pid = Some(42);
+        shell.args = vec!["-bash".to_string()];
+        assert_eq!(
+            render(&Long, &shell),
+            "name:       bash\n\
+             version:    5.3.9\n\
+             path:       /usr/bin/bash\n\
+             pid:        42\n\
+             mode:       login\n\
+             provenance: process\n\
+             confidence: high\n"
+        );
+        let mut shell = Shell::new("zsh", Source::Environment);
+        shell.warnings.push(Warning::EnvironmentShell);
+        let text = render(&Long, &shell);
+        assert!(text.contains("\npid:        -\nmode:       -\n"));
+        assert!(text.contains("\nconfidence: low\n"));
+        assert!(
+            text.ends_with(
+                "\nwarning:    [W003] shell from $SHELL, may not be the running shell\n"
+            )
+        );
+    }
+
+    #[test]
+    fn format_matches_renderers() {
+        let format: Format = "json".parse().unwrap();
+        assert_eq!(format.to_text(&bash()), render(&Json, &bash()));
+        assert_eq!(
+            format.to_text_all(&[dash(), bash()]),
+            render_all(&Json, &[dash(), bash()])
+        );
+        let template = Format::Template("{semver} {major}".to_string());
+        assert_eq!(template.to_text(&bash()), "5.3.9 5\n");
+        assert_eq!("long".parse::<Format>().unwrap(), Format::Long);
+        for name in ["xml", "template"] {
+            let err = name.parse::<Format>().unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn long_render_all_blank_line() {
+        let text = render_all(&Long, &[dash(), bash()]);
+        assert!(text.contains("confidence: high\n\nname:       bash\n"));
+    }
+
+    #[test]
+    fn error_record_json() {
+        let not_found = ShellNotFound {
+            chain: vec![(42, "make".to_string()), (7, "systemd".to_string())],
+            end: ChainEnd::SessionManager("systemd".to_string()),
+            nested_pid_namespace: false,
+        };
+        let err = io::Error::new(io::ErrorKind::NotFound, not_found);
+        assert_eq!(
+            ErrorRecord::from(&err).to_json(),
+            format!(
+                r#"{{"error":{{"kind":"not_found","message":"{err}","hops":2,"end":"session_manager","chain":[{{"pid":42,"comm":"make"}},{{"pid":7,"comm":"systemd"}}],"nested_pid_namespace":false}}}}"#
+            )
+        );
+        let err = io::Error::new(io::ErrorKind::InvalidInput, "bad \"req\"");
+        assert_eq!(
+            ErrorRecord::from(&err).to_json(),
+            r#"{"error":{"kind":"invalid_input","message":"bad \"req\"","hops":0,"end":null,"chain":[],"nested_pid_namespace":false}}"#
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Process tables captured elsewhere, for reproducing detection offline.
+//!
+//! A user's `ps -ef` output, e.g. attached to a bug report, is turned into a
+//! directory mimicking `/proc` with [`from_ps`]. Detection then runs against
+//! it with [`Detector::snapshot`](crate::Detector::snapshot), which also
+//! takes a copied `/proc` tree, without touching the live system.
+//!
+//! ```no_run
+//! use shellver::Detector;
+//! use shellver::snapshot;
+//!
+//! let ps = std::fs::read_to_string("ps.txt")?;
+//! snapshot::from_ps(&ps, None, "/tmp/snapshot".as_ref())?;
+//! let shell = Detector::new().snapshot("/tmp/snapshot").detect()?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Longest command name the kernel keeps in `/proc/<pid>/comm`.
+const COMM_LEN: usize = 15;
+
+/// Writes the processes listed in `ps` output as a `/proc`-like tree under
+/// `dir`, with a `comm`, `status` and `cmdline` file per process.
+///
+/// The header names the columns, so `ps -ef` and `ps -eo pid,ppid,args` both
+/// work as long as `PID`, `PPID` and a last `CMD`, `COMMAND` or `ARGS` column
+/// are present. Arguments are split at whitespace, since `ps` does not quote
+/// them. The walk starts at `start`, or else at the parent of the `ps`
+/// process itself, i.e. the shell it was run from.
+///
+/// # Errors
+///
+/// Returns [`io::ErrorKind::InvalidData`] when the header lacks a column,
+/// a pid is not a number or no start was given and `ps` is not listed, and
+/// any error from writing the files.
+pub fn from_ps(ps: &str, start: Option<u32>, dir: &Path) -> io::Result<()> {
+    let mut lines = ps.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<&str> = lines
+        .next()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect();
+    let column = |names: &[&str]| {
+        let found = header.iter().position(|name| names.contains(name));
+        found.ok_or_else(|| invalid_data(&format!("ps output lacks a {} column", names[0])))
+    };
+    let (pid_column, parent_column) = (column(&["PID"])?, column(&["PPID"])?);
+    let cmd_at = column(&["CMD", "COMMAND", "ARGS"])?;
+    if cmd_at + 1 != header.len() {
+        return Err(invalid_data("the command must be the last ps column"));
+    }
+    let mut caller = None;
+    for line in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let number = |at: usize| {
+            let field = fields.get(at).copied().unwrap_or_default();
+            field
+                .parse::<u32>()
+                .map_err(|_| invalid_data(&format!("bad pid {field:?} in ps output")))
+        };
+        let (pid, parent) = (number(pid_column)?, number(parent_column)?);
+        let args = fields.get(cmd_at..).unwrap_or_default();
+        let comm = comm(args.first().copied().unwrap_or_default());
+        if comm == "ps" {
+            caller = Some(parent);
+        }
+        let proc_dir = dir.join(pid.to_string());
+        fs::create_dir_all(&proc_dir)?;
+        fs::write(proc_dir.join("comm"), format!("{comm}\n"))?;
+        fs::write(
+            proc_dir.join("status"),
+            format!("Name:\t{comm}\nPid:\t{pid}\nPPid:\t{parent}\n"),
+        )?;
+        let mut cmdline = args.join("\0");
+        cmdline.push('\0');
+        fs::write(proc_dir.join("cmdline"), cmdline)?;
+    }
+    let start = start
+        .or(caller)
+        .ok_or_else(|| invalid_data("ps output does not list ps itself, give a start pid"))?;
+    fs::create_dir_all(dir.join("self"))?;
+    fs::write(dir.join("self/status"), format!("PPid:\t{start}\n"))
+}
+
+// The name the kernel would report for a command whose first argument is
+// `arg0`: without directory, login dash or the brackets of kernel threads.
+fn comm(arg0: &str) -> String {
+    let name = arg0.strip_prefix('[').map_or_else(
+        || arg0.rsplit('/').next().unwrap_or_default(),
+        |thread| thread.trim_end_matches(']'),
+    );
+    name.trim_start_matches('-')
+        .chars()
+        .take(COMM_LEN)
+        .collect()
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
@@ -0,0 +1,60 @@
+mod tests {
+    use crate::Detector;
+    use crate::snapshot::*;
+    use std::env;
+    use std::fs;
+    use std::io;
+
+    const PS: &str = "\
+UID          PID    PPID  C STIME TTY          TIME CMD
+root           1       0  0 09:00 ?        00:00:01 /sbin/init splash
+root           2       0  0 09:00 ?        00:00:00 [kthreadd]
+me           900       1  0 09:01 ?        00:00:00 /usr/bin/tmux new -s work
+me          1000     900  0 09:01 pts/0    00:00:00 -zsh
+me          1200    1000  0 09:02 pts/0    00:00:00 bash --norc
+me          1300    1200  0 09:03 pts/0    00:00:00 ps -ef
+";
+
+    fn snapshot_dir(name: &str) -> std::path::PathBuf {
+        env::temp_dir().join(format!("shellver-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn from_ps_writes_proc_tree() {
+        let dir = snapshot_dir("ps-tree");
+        from_ps(PS, None, &dir).unwrap();
+        let read = |path: &str| fs::read_to_string(dir.join(path)).unwrap();
+        assert_eq!(read("self/status"), "PPid:\t1200\n");
+        assert_eq!(read("1000/comm"), "zsh\n");
+        assert_eq!(read("2/comm"), "kthreadd\n");
+        assert_eq!(read("900/cmdline"), "/usr/bin/tmux\0new\0-s\0work\0");
+        assert_eq!(
+            read("1200/status"),
+            "Name:\tbash\nPid:\t1200\nPPid:\t1000\n"
+        );
+        let shell = Detector::new().snapshot(&dir).detect().unwrap();
+        assert_eq!((shell.name(), shell.pid()), ("bash", Some(1200)));
+        assert_eq!(shell.version(), None);
+        from_ps(PS, Some(1000), &dir).unwrap();
+        let shell = Detector::new().snapshot(&dir).detect().unwrap();
+        assert_eq!((shell.name(), shell.pid()), ("zsh", Some(1000)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn from_ps_rejects_bad_input() {
+        let dir = snapshot_dir("ps-bad");
+        let without_ps = PS.lines().take(6).collect::<Vec<_>>().join("\n");
+        let inputs = [
+            "USER PID %CPU COMMAND\nme 1 0.0 init\n",
+            "PID PPID CMD\nabc 1 sh\n",
+            "PID CMD PPID\n1 sh 0\n",
+            without_ps.as_str(),
+        ];
+        for ps in inputs {
+            let err = from_ps(ps, None, &dir).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{ps}");
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,4 +1,10 @@
-use std::process::Command;
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 
 #[test]
 fn runs_ok() {
@@ -7,3 +13,643 @@ fn runs_ok() {
         .unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn unknown_command_fails() {
+    let status = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("bogus")
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}
+
+#[test]
+fn proc_root_missing_fails() {
+    let status = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .args(["--proc-root", "/nonexistent/proc"])
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(4));
+}
+
+#[test]
+fn proc_root_live_ok() {
+    let status = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("--proc-root=/proc")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn output_json_ok() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .args(["--output", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.starts_with(b"{\"schema\":2,\"name\":"));
+}
+
+#[test]
+fn json_flag_ok() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("--json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.starts_with(b"{\"schema\":2,\"name\":"));
+}
+
+#[test]
+fn porcelain_is_tab_separated() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("--porcelain=v1")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches('\t').count(), 2);
+    let status = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("--porcelain=v9")
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}
+
+#[test]
+fn output_unknown_fails() {
+    let status = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .args(["--output", "xml"])
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}
+
+#[test]
+fn survey_json_is_array() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .args(["survey", "-o", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.starts_with(b"["));
+}
+
+#[test]
+fn closed_stdout_exits_ok() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("survey")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    drop(child.stdout.take());
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn ps_output_from_stdin() {
+    let ps = "UID PID PPID C STIME TTY TIME CMD\n\
+              me 50 1 0 09:00 ? 00:00:00 -fish\n\
+              me 55 50 0 09:01 pts/0 00:00:00 bash\n\
+              me 60 55 0 09:02 pts/0 00:00:00 ps -ef\n";
+    let run = |flags: &[&str]| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_shellver"))
+            .args(["--ps", "-", "--template", "{name} {pid}"])
+            .args(flags)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(ps.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+    let all = run(&["--all"]);
+    assert!(all.status.success());
+    assert_eq!(all.stdout, b"bash 55\nfish 50\n");
+    assert_eq!(run(&["--pid", "50"]).stdout, b"fish 50\n");
+    // The snapshot cannot change, so watching it ends after one result.
+    assert_eq!(run(&["--watch=0.1"]).stdout, b"bash 55\n");
+    assert!(!run(&["--pid", "fifty"]).status.success());
+}
+
+#[test]
+fn env_override_wins() {
+    let run = |shell: &str| {
+        Command::new(env!("CARGO_BIN_EXE_shellver"))
+            .args(["--template", "{name} {version} {source}"])
+            .env("SHELLVER_SHELL", shell)
+            .env("SHELLVER_VERSION", "5.9")
+            .output()
+            .unwrap()
+    };
+    let forced = run("zsh");
+    assert!(forced.status.success());
+    assert_eq!(forced.stdout, b"zsh 5.9 override\n");
+    let unsupported = run("python3");
+    assert_eq!(unsupported.status.code(), Some(1));
+}
+
+#[test]
+fn detect_command_matches_default() {
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_shellver"))
+            .args(args)
+            .args(["--template", "{name} {version} {source}"])
+            .env("SHELLVER_SHELL", "zsh")
+            .env("SHELLVER_VERSION", "5.9")
+            .output()
+            .unwrap()
+    };
+    let detected = run(&["detect"]);
+    assert!(detected.status.success());
+    assert_eq!(detected.stdout, b"zsh 5.9 override\n");
+    assert_eq!(detected.stdout, run(&[]).stdout);
+}
+
+#[test]
+fn strict_json_reports_missing_version() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .args(["--json", "--strict"])
+        .env("SHELLVER_SHELL", "dash")
+        .env("PATH", "/nonexistent")
+        .env_remove("LC_ALL")
+        .env_remove("LC_MESSAGES")
+        .env("LANG", "de_DE.UTF-8")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.starts_with(
+            r#"{"error":{"kind":"no_version","message":"the version of dash is unknown","#
+        ),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn json_reports_parse_errors() {
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+            .args(args)
+            .env_remove("LC_ALL")
+            .env_remove("LC_MESSAGES")
+            .env("LANG", "hu_HU.UTF-8")
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        String::from_utf8(output.stderr).unwrap()
+    };
+    let expected = r#"{"error":{"kind":"invalid_input","message":"unknown option: --bogus","#;
+    for args in [&["--json", "--bogus"][..], &["-o", "json", "--bogus"]] {
+        let stderr = run(args);
+        assert!(stderr.starts_with(expected), "{args:?}: {stderr}");
+    }
+    let plain = run(&["--bogus"]);
+    assert!(plain.contains("ismeretlen kapcsoló: --bogus"), "{plain}");
+}
+
+#[test]
+fn long_output_ok() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("-l")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("name:"));
+    assert!(stdout.contains("\nconfidence: "));
+}
+
+#[test]
+fn major_is_numeric() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("--major")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.trim_end().chars().all(|ch| ch.is_ascii_digit()));
+}
+
+#[test]
+fn fingerprint_is_hex() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("--fingerprint")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let fingerprint = stdout.trim_end();
+    assert_eq!(fingerprint.len(), 16);
+    assert!(fingerprint.chars().all(|ch| ch.is_ascii_hexdigit()));
+}
+
+#[test]
+fn compare_lists_capabilities() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .args(["compare", "sh", "/bin/sh"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("\nversion "));
+    assert!(stdout.contains("\nbrace_expansion "));
+}
+
+#[test]
+fn compare_needs_two_fails() {
+    let status = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .args(["compare", "sh"])
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}
+
+#[test]
+fn help_lists_commands() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("--help")
+        .env("LC_ALL", "C")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("Usage: shellver"));
+    assert!(stdout.contains("\n  require [shell] <req> "));
+}
+
+#[test]
+fn require_sets_exit_status() {
+    let require = |requirement: &str| {
+        let status = Command::new(env!("CARGO_BIN_EXE_shellver"))
+            .args(["require", requirement])
+            .status()
+            .unwrap();
+        status.success()
+    };
+    assert!(require("*"));
+    assert!(!require("<0"));
+    assert!(!require("bogus"));
+    let status = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .args(["require", "sh", "*"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let status = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .args(["require", "no-such-shell", "*"])
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}
+
+#[test]
+fn ancestors_lists_chain() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("ancestors")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("PID "));
+    assert!(stdout.lines().count() > 1);
+}
+
+#[test]
+fn doctor_reports_detection() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("doctor")
+        .env("LC_ALL", "C")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("ok   detected "), "{stdout}");
+}
+
+#[test]
+fn env_prints_assignments() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("env")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 3);
+    assert!(stdout.contains("SHELLVER_VERSION"));
+}
+
+#[test]
+fn shell_flag_skips_detection() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .args(["--shell", "bash", "--template", "{name}"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"bash\n");
+    let status = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .args(["--shell", "no-such-shell"])
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}
+
+#[test]
+fn no_version_prints_name_only() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .args(["--no-version", "--template", "{name}|{version}"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.ends_with(b"|\n"));
+}
+
+#[test]
+fn timeout_flag_parsed() {
+    let status = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .args(["--timeout", "1.5"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    for bad in ["0", "-1", "soon"] {
+        let status = Command::new(env!("CARGO_BIN_EXE_shellver"))
+            .args(["--timeout", bad])
+            .status()
+            .unwrap();
+        assert!(!status.success(), "{bad}");
+    }
+}
+
+#[test]
+fn verbosity_controls_log() {
+    let run = |flags: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_shellver"))
+            .args(flags)
+            .env("SHELLVER_LOG", "info")
+            .output()
+            .unwrap()
+    };
+    let debug = String::from_utf8(run(&["-vv"]).stderr).unwrap();
+    assert!(debug.contains("shellver: read "), "{debug}");
+    assert!(debug.contains("shellver: hop "), "{debug}");
+    let info = String::from_utf8(run(&[]).stderr).unwrap();
+    assert!(!info.contains("shellver: hop "), "{info}");
+    assert!(run(&["-q"]).stderr.is_empty());
+}
+
+#[test]
+fn color_follows_flag_and_no_color() {
+    let run = |flags: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_shellver"))
+            .args(flags)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap()
+    };
+    assert!(run(&["--color=always"]).stdout.starts_with(b"\x1b[1;36m"));
+    assert!(!run(&["--color", "auto"]).stdout.contains(&0x1b));
+    assert!(!run(&["--color=never"]).stdout.contains(&0x1b));
+    assert!(!run(&["--color=sometimes"]).status.success());
+}
+
+#[test]
+fn watch_prints_first_result() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("--watch=0.1")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(line.ends_with('\n'));
+    assert!(line.len() > 1);
+}
+
+#[test]
+fn completions_cover_commands_and_flags() {
+    let run = |shell: &str| {
+        Command::new(env!("CARGO_BIN_EXE_shellver"))
+            .args(["completions", shell])
+            .output()
+            .unwrap()
+    };
+    let bash = String::from_utf8(run("bash").stdout).unwrap();
+    assert!(bash.contains("complete -F _shellver shellver"), "{bash}");
+    assert!(
+        bash.contains(" doctor ") && bash.contains(" --watch"),
+        "{bash}"
+    );
+    let zsh = String::from_utf8(run("zsh").stdout).unwrap();
+    assert!(zsh.starts_with("#compdef shellver\n"), "{zsh}");
+    let fish = String::from_utf8(run("fish").stdout).unwrap();
+    assert!(
+        fish.contains("complete -c shellver -s a -l all\n"),
+        "{fish}"
+    );
+    assert!(!run("tcsh").status.success());
+}
+
+#[test]
+fn dump_redacts_chain() {
+    let root = env::temp_dir().join(format!("shellver-dump-{}", std::process::id()));
+    fs::create_dir_all(root.join("self")).unwrap();
+    fs::create_dir_all(root.join("10")).unwrap();
+    fs::write(root.join("self/status"), "PPid:\t10\n").unwrap();
+    fs::write(root.join("10/comm"), "bash\n").unwrap();
+    fs::write(
+        root.join("10/status"),
+        "Name:\tbash\nUmask:\t0022\nPPid:\t1\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("10/cmdline"),
+        "bash\0--rcfile=/x\0-c\0echo token\0",
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("--proc-root")
+        .arg(&root)
+        .arg("dump")
+        .env("HOME", "/home/me")
+        .env("SHELL", "/home/me/bin/fish")
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&root).unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("SHELL: ~/bin/fish\n"), "{stdout}");
+    assert!(stdout.contains("  status: PPid: 1\n"), "{stdout}");
+    assert!(!stdout.contains("Umask"), "{stdout}");
+    assert!(
+        stdout.contains("  cmdline: bash --rcfile=<redacted> -c <redacted>\n"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn list_shows_supported_shells() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("list")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("NAME "));
+    assert!(stdout.contains("\nbash "));
+    assert!(stdout.contains(" bash --version "));
+}
+
+#[test]
+fn which_all_versions_lists_paths() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .args(["which", "sh", "--all-versions"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.lines().all(|line| line.starts_with('/')));
+}
+
+#[test]
+fn which_requirement_filters() {
+    let which = |requirement: &str| {
+        let status = Command::new(env!("CARGO_BIN_EXE_shellver"))
+            .args(["which", "sh", requirement])
+            .status()
+            .unwrap();
+        status.success()
+    };
+    assert!(which("*"));
+    assert!(!which("<0"));
+}
+
+// A `bash` on PATH that hangs, for the flags that must keep it from blocking.
+fn slow_bash_path(name: &str) -> (PathBuf, String) {
+    let dir = env::temp_dir().join(format!("shellver-{name}-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let bash = dir.join("bash");
+    fs::write(&bash, "#!/bin/sh\nsleep 5\n").unwrap();
+    fs::set_permissions(&bash, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!("{}:{}", dir.display(), env::var("PATH").unwrap_or_default());
+    (dir, path)
+}
+
+#[test]
+fn timeout_bounds_installation_lookups() {
+    let (dir, path) = slow_bash_path("slow-timeout");
+    let run = |flags: &[&str]| {
+        let started = Instant::now();
+        let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+            .args(flags)
+            .args(["--timeout", "0.3"])
+            .env("PATH", &path)
+            .output()
+            .unwrap();
+        (output, started.elapsed())
+    };
+    let (which, which_took) = run(&["which", "bash"]);
+    let (shell, shell_took) = run(&["--shell", "bash"]);
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(which.stdout.starts_with(dir.to_string_lossy().as_bytes()));
+    assert!(which_took < Duration::from_secs(3), "{which_took:?}");
+    assert_eq!(shell.stdout, b"bash \n");
+    assert!(shell_took < Duration::from_secs(3), "{shell_took:?}");
+}
+
+#[test]
+fn no_version_skips_installation_lookups() {
+    let (dir, path) = slow_bash_path("slow-no-version");
+    let started = Instant::now();
+    let run = |flags: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_shellver"))
+            .args(flags)
+            .arg("--no-version")
+            .env("PATH", &path)
+            .output()
+            .unwrap()
+    };
+    let shell = run(&["--shell", "bash"]);
+    let which = run(&["which", "bash"]);
+    let require = run(&["require", "bash", "*"]);
+    let took = started.elapsed();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(shell.stdout, b"bash \n");
+    assert_eq!(
+        which.stdout,
+        format!("{} \n", dir.join("bash").display()).as_bytes()
+    );
+    assert!(require.status.success());
+    assert!(took < Duration::from_secs(3), "{took:?}");
+}
+
+#[test]
+fn info_reports_activation() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shellver"))
+        .arg("info")
+        .env("VIRTUAL_ENV", "/src/app/.venv")
+        .env_remove("CONDA_DEFAULT_ENV")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("venv:       /src/app/.venv\n"));
+    assert!(stdout.contains("conda:      -\n"));
+}
+
+#[test]
+fn env_fallback_after_orphaned_walk() {
+    let root = env::temp_dir().join(format!("shellver-orphan-{}", std::process::id()));
+    fs::create_dir_all(root.join("self")).unwrap();
+    fs::write(root.join("self/status"), "PPid:\t1\n").unwrap();
+    let run = |flags: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_shellver"))
+            .arg("--proc-root")
+            .arg(&root)
+            .args(flags)
+            .env("SHELL", "/bin/sh")
+            .env("LANG", "C")
+            .output()
+            .unwrap()
+    };
+    let strict = run(&["--strict"]);
+    let json = run(&["--strict", "--json"]);
+    let fallback = run(&[]);
+    let explicit = run(&["--env-fallback"]);
+    let default = run(&["--strict", "--fallback", "bash", "--template", "{name}"]);
+    let missing = run(&[
+        "--strict",
+        "--fallback",
+        "nosuchsh",
+        "--template",
+        "{name} {source}",
+    ]);
+    fs::remove_dir_all(&root).unwrap();
+    assert!(default.status.success());
+    assert_eq!(default.stdout, b"bash\n");
+    assert!(missing.status.success());
+    assert_eq!(missing.stdout, b"nosuchsh default\n");
+    assert_eq!(strict.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&strict.stderr).contains("reached init"));
+    assert_eq!(json.status.code(), Some(3));
+    let stderr = String::from_utf8(json.stderr).unwrap();
+    assert!(
+        stderr.starts_with(r#"{"error":{"kind":"not_found","#),
+        "{stderr}"
+    );
+    assert!(stderr.contains(r#""end":"init""#), "{stderr}");
+    // Without a version the fallback still succeeds, with its own status.
+    assert!(matches!(fallback.status.code(), Some(0 | 2)));
+    assert!(fallback.stdout.starts_with(b"sh "));
+    assert!(String::from_utf8_lossy(&fallback.stderr).contains("[W003]"));
+    assert!(matches!(explicit.status.code(), Some(0 | 2)));
+}